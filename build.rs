@@ -1,6 +1,9 @@
 fn main() {
     println!("cargo:rerun-if-changed=build.rs");
-    println!("cargo:rerun-if-changed=native/picked.m");
+    println!("cargo:rerun-if-changed=native/picker.m");
+    if std::env::var("CARGO_CFG_TARGET_OS").as_deref() != Ok("ios") {
+        return;
+    }
     cc::Build::new()
         .file("native/picker.m")
         .flag("-fobjc-arc")
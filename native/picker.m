@@ -1,86 +1,1037 @@
+#include <stdatomic.h>
 #include <stdbool.h>
 #include <stddef.h>
+#include <stdint.h>
+#include <string.h>
+#include <math.h>
 
 #include <Foundation/Foundation.h>
 #include <UIKit/UIKit.h>
 #include <UniformTypeIdentifiers/UniformTypeIdentifiers.h>
 
+// Keep in sync with `PickMessageKind` in `src/backend/mod.rs`.
+typedef enum {
+  PickMessageFile = 0,
+  PickMessageFileError = 1,
+  PickMessageFinished = 2,
+  PickMessageCancelled = 3,
+  PickMessageFailed = 4,
+  PickMessageExported = 5,
+  PickMessageChunk = 6,
+  PickMessageEvent = 7,
+} PickMessageKind;
+
+// Keep in sync with `RawPickErrorKind` in `src/backend/mod.rs`.
+typedef enum {
+  PickErrorAccessDenied = 0,
+  PickErrorCoordinationFailed = 1,
+  PickErrorReadFailed = 2,
+  PickErrorUnsupportedType = 3,
+  PickErrorUnsupportedPlatform = 4,
+  PickErrorBookmarkFailed = 5,
+  PickErrorFileTooLarge = 6,
+  PickErrorBudgetExceeded = 7,
+  PickErrorNoPresenter = 8,
+  PickErrorBusy = 9,
+  PickErrorWriteFailed = 10,
+  PickErrorInvalidInput = 11,
+} PickErrorKind;
+
+// Keep in sync with `RawPickError` in `src/backend/mod.rs`.
+typedef struct {
+  PickErrorKind kind;
+  const char *domain;
+  ptrdiff_t code;
+  const char *description;
+  uint64_t size;
+  uint64_t limit;
+} RawPickError;
+
+// Keep in sync with `RawPickEventKind` in `src/backend/mod.rs`.
+typedef enum {
+  PickEventPresented = 0,
+  PickEventDismissed = 1,
+  PickEventFileStarted = 2,
+  PickEventProgress = 3,
+  PickEventFileFinished = 4,
+} PickEventKind;
+
+// Keep in sync with `RawPickEvent` in `src/backend/mod.rs`.
+typedef struct {
+  PickEventKind kind;
+  size_t index;
+  const char *name;
+  uint64_t completed;
+  int64_t total;
+} RawPickEvent;
+
+// Keep in sync with `RawPickedFile` in `src/backend/mod.rs`.
+typedef struct {
+  const void *data;
+  size_t len;
+  void *owner;
+  void (*release)(void *);
+  const char *name;
+  const char *url;
+  const char *path;
+  const void *bookmark;
+  size_t bookmark_len;
+  const char *content_type;
+  int64_t size;
+  double created;
+  double modified;
+  const char *provider;
+} RawPickedFile;
+
+// Keep in sync with `RawPickerOptions` in `src/backend/uikit.rs`.
+typedef struct {
+  const char *const *extensions;
+  size_t types_len;
+  const char *const *identifiers;
+  size_t identifiers_len;
+  bool allow_multiple;
+  size_t max_selection;
+  const char *initial_directory;
+  bool show_extensions;
+  bool as_copy;
+  ptrdiff_t presentation_style;
+  bool folder;
+  bool bookmarks;
+  bool streaming;
+  uint64_t max_file_size;
+  uint64_t byte_budget;
+  size_t concurrent_reads;
+  bool events;
+} PickerOptions;
+
+// Keep in sync with `RawWriteKind` in `src/backend/uikit.rs`.
+typedef enum {
+  WriteOperationWrite = 0,
+  WriteOperationReplace = 1,
+  WriteOperationCreate = 2,
+  WriteOperationMove = 3,
+  WriteOperationDelete = 4,
+} WriteOperationKind;
+
+// Keep in sync with `RawWriteOperation` in `src/backend/uikit.rs`.
+typedef struct {
+  WriteOperationKind kind;
+  const char *path;
+  const char *destination;
+  const void *data;
+  size_t len;
+} RawWriteOperation;
+
+typedef void (^PickClosure)(PickMessageKind, const void *, size_t);
+
+// The outcome of reading a file, which is reported later to the closure.
+typedef void (^Report)(PickClosure);
+
+// Decides from the size, or nil if unknown, whether the file at the URL is
+// read. It returns nil if so, or else the error to report.
+typedef Report (^SizeCheck)(NSURL *, NSNumber *);
+
+static double time_since_1970(NSDate *date) {
+  return date ? [date timeIntervalSince1970] : NAN;
+}
+
+static void release_data(void *owner) { CFRelease(owner); }
+
+// The content is handed over to the Rust side without copying, which releases
+// the `NSData` once the last `FileHandle` sharing it drops.
+static void send_file(PickClosure closure, NSURL *url, NSURL *accessedUrl,
+                      NSData *data, NSData *bookmark) {
+  NSDictionary<NSURLResourceKey, id> *values = [accessedUrl
+      resourceValuesForKeys:@[
+        NSURLNameKey, NSURLContentTypeKey, NSURLFileSizeKey,
+        NSURLCreationDateKey, NSURLContentModificationDateKey,
+        NSURLIsUbiquitousItemKey, NSURLUbiquitousItemContainerDisplayNameKey
+      ]
+                      error:nil];
+  NSString *name = values[NSURLNameKey] ?: [url lastPathComponent];
+  UTType *type = values[NSURLContentTypeKey];
+  NSNumber *size = values[NSURLFileSizeKey];
+  NSString *provider = nil;
+  if ([values[NSURLIsUbiquitousItemKey] boolValue]) {
+    provider = values[NSURLUbiquitousItemContainerDisplayNameKey]
+                   ?: @"iCloud Drive";
+  }
+  RawPickedFile raw = {
+      .data = [data bytes],
+      .len = [data length],
+      .owner = data ? (__bridge_retained void *)data : NULL,
+      .release = data ? release_data : NULL,
+      .name = [name UTF8String],
+      .url = [[url absoluteString] UTF8String],
+      .path = [url isFileURL] ? [url fileSystemRepresentation] : NULL,
+      .bookmark = [bookmark bytes],
+      .bookmark_len = [bookmark length],
+      .content_type = type ? [type.identifier UTF8String] : NULL,
+      .size = size ? [size longLongValue] : -1,
+      .created = time_since_1970(values[NSURLCreationDateKey]),
+      .modified = time_since_1970(values[NSURLContentModificationDateKey]),
+      .provider = provider ? [provider UTF8String] : NULL,
+  };
+  closure(PickMessageFile, &raw, sizeof(raw));
+}
+
+static void send_error(PickClosure closure, PickMessageKind message,
+                       PickErrorKind kind, NSError *error) {
+  RawPickError raw = {
+      .kind = kind,
+      .domain = error ? [error.domain UTF8String] : NULL,
+      .code = error ? error.code : 0,
+      .description = error ? [error.localizedDescription UTF8String] : NULL,
+  };
+  closure(message, &raw, sizeof(raw));
+}
+
+static void send_size_error(PickClosure closure, PickErrorKind kind,
+                            NSURL *url, uint64_t size, uint64_t limit) {
+  RawPickError raw = {
+      .kind = kind,
+      .domain = NULL,
+      .code = 0,
+      .description = [[url lastPathComponent] UTF8String],
+      .size = size,
+      .limit = limit,
+  };
+  closure(PickMessageFileError, &raw, sizeof(raw));
+}
+
+// Must be called while accessing `url`. A URL which couldn't be bookmarked
+// is still reported, without a bookmark.
+static NSData *create_bookmark(NSURL *url) {
+  return [url bookmarkDataWithOptions:0
+       includingResourceValuesForKeys:nil
+                        relativeToURL:nil
+                                error:nil];
+}
+
+static Report error_report(PickErrorKind kind, NSError *error) {
+  return ^(PickClosure closure) {
+    send_error(closure, PickMessageFileError, kind, error);
+  };
+}
+
+// Reads `url` with coordination, resulting in either `PickMessageFile` or
+// `PickMessageFileError`. The caller keeps the security-scoped access. The
+// size is checked, if `check` isn't nil, before reading the content.
+static Report read_report(NSURL *url, NSData *bookmark, SizeCheck check) {
+  NSFileCoordinator *coordinator =
+      [[NSFileCoordinator alloc] initWithFilePresenter:nil];
+  NSError *coordinationError = nil;
+  __block Report report = nil;
+  [coordinator
+      coordinateReadingItemAtURL:url
+                         options:NSFileCoordinatorReadingWithoutChanges
+                           error:&coordinationError
+                      byAccessor:^(NSURL *newUrl) {
+                        if (check) {
+                          NSNumber *size = nil;
+                          [newUrl getResourceValue:&size
+                                            forKey:NSURLFileSizeKey
+                                             error:nil];
+                          report = check(newUrl, size);
+                          if (report) {
+                            return;
+                          }
+                        }
+                        NSError *readError = nil;
+                        NSData *data = [NSData
+                            dataWithContentsOfURL:newUrl
+                                          options:NSDataReadingMappedIfSafe
+                                            error:&readError];
+                        if (data) {
+                          report = ^(PickClosure closure) {
+                            send_file(closure, url, newUrl, data, bookmark);
+                          };
+                        } else {
+                          report = error_report(PickErrorReadFailed, readError);
+                        }
+                      }];
+  return report ?: error_report(PickErrorCoordinationFailed, coordinationError);
+}
+
+// Reads `url` with coordination, reporting to `closure` at once.
+static void read_coordinated(PickClosure closure, NSURL *url, NSData *bookmark,
+                             SizeCheck check) {
+  read_report(url, bookmark, check)(closure);
+}
+
+// Calls the handler with the bytes done whenever the fraction completed of a
+// progress changes them, on the thread changing it, until it is invalidated.
+@interface ProgressObserver : NSObject
+- (instancetype)initWithProgress:(NSProgress *)progress
+                           total:(int64_t)total
+                         handler:(void (^)(uint64_t))handler;
+- (void)invalidate;
+@end
+
+@implementation ProgressObserver {
+  NSProgress *_progress;
+  int64_t _total;
+  uint64_t _completed;
+  void (^_handler)(uint64_t);
+}
+
+- (instancetype)initWithProgress:(NSProgress *)progress
+                           total:(int64_t)total
+                         handler:(void (^)(uint64_t))handler {
+  if ((self = [super init])) {
+    _progress = progress;
+    _total = total;
+    _completed = UINT64_MAX;
+    _handler = handler;
+    [progress addObserver:self
+               forKeyPath:@"fractionCompleted"
+                  options:NSKeyValueObservingOptionInitial
+                  context:NULL];
+  }
+  return self;
+}
+
+- (void)observeValueForKeyPath:(NSString *)keyPath
+                      ofObject:(id)object
+                        change:(NSDictionary<NSKeyValueChangeKey, id> *)change
+                       context:(void *)context {
+  double fraction = MIN(MAX(((NSProgress *)object).fractionCompleted, 0), 1);
+  uint64_t completed = (uint64_t)llround(fraction * (double)_total);
+  @synchronized(self) {
+    if (!_progress || completed == _completed) {
+      return;
+    }
+    _completed = completed;
+  }
+  _handler(completed);
+}
+
+- (void)invalidate {
+  @synchronized(self) {
+    if (_progress) {
+      [_progress removeObserver:self forKeyPath:@"fractionCompleted"];
+      _progress = nil;
+    }
+  }
+}
+@end
+
 @interface FilePickerDelegate : NSObject <UIDocumentPickerDelegate> {
-  void (^closure)(NSData *);
+  PickClosure closure;
+  // Also read by the background reads, to skip them after a dismissal.
+  atomic_bool _finished;
 }
-@property void (^closure)(NSData *);
-- (instancetype)initWithClosure:(void (^)(NSData *))closure;
+@property PickClosure closure;
+@property(nonatomic) bool finished;
+@property(strong) NSArray<NSURL *> *urls;
+@property NSUInteger next;
+@property NSUInteger requested;
+@property NSUInteger started;
+@property NSUInteger reading;
+@property size_t concurrentReads;
+@property bool events;
+@property(strong) NSMutableDictionary<NSNumber *, Report> *reports;
+@property bool asCopy;
+@property bool exporting;
+@property size_t maxSelection;
+@property bool folder;
+@property(strong) NSMutableArray<NSURL *> *accessedUrls;
+@property bool bookmarks;
+@property bool streaming;
+@property uint64_t maxFileSize;
+@property uint64_t byteBudget;
+@property(weak) UIViewController *browser;
+- (instancetype)initWithClosure:(PickClosure)closure;
+- (void)finish;
+- (void)sendEvent:(RawPickEvent)event;
+- (void)requestNext;
+- (void)dismiss;
 @end
 
 @implementation FilePickerDelegate
 @synthesize closure;
 
-- (instancetype)initWithClosure:(void (^)(NSData *))c {
+- (bool)finished {
+  return atomic_load(&_finished);
+}
+
+- (void)setFinished:(bool)finished {
+  atomic_store(&_finished, finished);
+}
+
+- (instancetype)initWithClosure:(PickClosure)c {
   if ([self init]) {
     self.closure = c;
+    self.finished = false;
+    self.urls = nil;
+    self.next = 0;
+    self.requested = 0;
+    self.started = 0;
+    self.reading = 0;
+    self.concurrentReads = 1;
+    self.events = false;
+    self.reports = [NSMutableDictionary dictionary];
+    self.asCopy = false;
+    self.exporting = false;
+    self.maxSelection = SIZE_MAX;
+    self.folder = false;
+    self.accessedUrls = [NSMutableArray array];
+    self.bookmarks = false;
+    self.streaming = false;
+    self.maxFileSize = UINT64_MAX;
+    self.byteBudget = UINT64_MAX;
   }
   return self;
 }
 
+- (void)finish {
+  // The callback data is freed by the Rust side on `PickMessageFinished`.
+  if (!self.finished) {
+    self.finished = true;
+    self.closure(PickMessageFinished, NULL, 0);
+  }
+}
+
+// Must be called on the main thread.
+- (void)sendEvent:(RawPickEvent)event {
+  if (self.events && !self.finished) {
+    self.closure(PickMessageEvent, &event, sizeof(event));
+  }
+}
+
+// Reports the bytes of the file at `index` done by `progress`, which includes
+// the download of a cloud document by the coordinated read. The events are
+// delivered on the main queue before the outcome of the read.
+- (ProgressObserver *)observeProgress:(NSProgress *)progress
+                                index:(NSUInteger)index
+                                total:(int64_t)total {
+  if (!self.events || total < 0) {
+    return nil;
+  }
+  return [[ProgressObserver alloc]
+      initWithProgress:progress
+                 total:total
+               handler:^(uint64_t completed) {
+                 dispatch_async(dispatch_get_main_queue(), ^{
+                   [self sendEvent:(RawPickEvent){
+                                       .kind = PickEventProgress,
+                                       .index = index,
+                                       .completed = completed,
+                                       .total = total,
+                                   }];
+                 });
+               }];
+}
+
+// Called on a background queue.
+- (Report)readURL:(NSURL *)url index:(NSUInteger)index {
+  // Copies are owned by the app and aren't security-scoped.
+  bool accessing = [url startAccessingSecurityScopedResource];
+  if (!accessing && !self.asCopy) {
+    return error_report(PickErrorAccessDenied, nil);
+  }
+  uint64_t maxFileSize = self.maxFileSize;
+  SizeCheck check = ^Report(NSURL *fileUrl, NSNumber *size) {
+    if (!size) {
+      return nil;
+    }
+    uint64_t n = [size unsignedLongLongValue];
+    if (n > maxFileSize) {
+      return ^(PickClosure closure) {
+        send_size_error(closure, PickErrorFileTooLarge, fileUrl, n,
+                        maxFileSize);
+      };
+    }
+    // The files read at once are charged in the order they are checked.
+    @synchronized(self) {
+      uint64_t budget = self.byteBudget;
+      if (n > budget) {
+        return ^(PickClosure closure) {
+          send_size_error(closure, PickErrorBudgetExceeded, fileUrl, n,
+                          budget);
+        };
+      }
+      if (budget != UINT64_MAX) {
+        self.byteBudget = budget - n;
+      }
+    }
+    return nil;
+  };
+  // The coordinated read downloads a cloud document before its accessor is
+  // called, and so a known size is checked before, and the size is checked
+  // in the accessor only if it is unknown until then.
+  NSNumber *size = nil;
+  [url getResourceValue:&size forKey:NSURLFileSizeKey error:nil];
+  if (size) {
+    Report rejected = check(url, size);
+    if (rejected) {
+      if (accessing) {
+        [url stopAccessingSecurityScopedResource];
+      }
+      return rejected;
+    }
+  }
+  int64_t total = size ? [size longLongValue] : -1;
+  // The coordinated read attaches the download, if any, as a child of the
+  // current progress, and resigning completes it.
+  NSProgress *progress =
+      [NSProgress progressWithTotalUnitCount:MAX(total, (int64_t)1)];
+  ProgressObserver *observer = [self observeProgress:progress
+                                               index:index
+                                               total:total];
+  [progress becomeCurrentWithPendingUnitCount:progress.totalUnitCount];
+  Report report = read_report(
+      url, self.bookmarks ? create_bookmark(url) : nil, size ? nil : check);
+  [progress resignCurrent];
+  [observer invalidate];
+  if (accessing) {
+    [url stopAccessingSecurityScopedResource];
+  }
+  return report;
+}
+
+// Reads the file at `index` on a background queue, and stores its report to
+// be delivered in order.
+- (void)startReading:(NSUInteger)index {
+  NSURL *url = self.urls[index];
+  self.reading += 1;
+  [self sendEvent:(RawPickEvent){
+                      .kind = PickEventFileStarted,
+                      .index = index,
+                      .name = [[url lastPathComponent] UTF8String],
+                      .total = -1,
+                  }];
+  dispatch_async(dispatch_get_global_queue(QOS_CLASS_USER_INITIATED, 0), ^{
+    Report report = self.finished ? nil : [self readURL:url index:index];
+    dispatch_async(dispatch_get_main_queue(), ^{
+      self.reading -= 1;
+      [self sendEvent:(RawPickEvent){
+                          .kind = PickEventFileFinished,
+                          .index = index,
+                          .total = -1,
+                      }];
+      if (report) {
+        self.reports[@(index)] = report;
+      }
+      [self pump];
+    });
+  });
+}
+
+// Reports the document without content, keeping the access to it until the
+// delegate is released, so that it could be read later with
+// `picker_read_file` or `picker_read_range`.
+- (void)openInPlace:(NSURL *)url {
+  bool accessing = [url startAccessingSecurityScopedResource];
+  if (!accessing && !self.asCopy) {
+    send_error(self.closure, PickMessageFileError, PickErrorAccessDenied, nil);
+    return;
+  }
+  if (accessing) {
+    [self.accessedUrls addObject:url];
+  }
+  send_file(self.closure, url, url, nil,
+            self.bookmarks ? create_bookmark(url) : nil);
+}
+
+- (void)dealloc {
+  for (NSURL *url in self.accessedUrls) {
+    [url stopAccessingSecurityScopedResource];
+  }
+}
+
+// Delivers the files in order, as many as the Rust side has requested, so
+// that a slow consumer holds back the reading of the following files. The
+// files are read off the main thread, at most `concurrentReads` at once, and
+// at most `concurrentReads - 1` ahead of the requests.
+- (void)pump {
+  if (self.finished || !self.urls) {
+    return;
+  }
+  if (self.folder || self.streaming) {
+    while (self.requested > 0 && self.next < self.urls.count) {
+      self.requested -= 1;
+      NSURL *url = self.urls[self.next];
+      self.next += 1;
+      [self openInPlace:url];
+    }
+  } else {
+    while (self.requested > 0 && self.next < self.urls.count) {
+      Report report = self.reports[@(self.next)];
+      if (!report) {
+        break;
+      }
+      [self.reports removeObjectForKey:@(self.next)];
+      self.requested -= 1;
+      self.next += 1;
+      report(self.closure);
+      if (self.finished) {
+        return;
+      }
+    }
+    NSUInteger window = self.next + self.requested + self.concurrentReads - 1;
+    while (self.reading < self.concurrentReads &&
+           self.started < MIN(window, self.urls.count)) {
+      [self startReading:self.started];
+      self.started += 1;
+    }
+  }
+  if (self.next >= self.urls.count) {
+    [self finish];
+  }
+}
+
+- (void)requestNext {
+  self.requested += 1;
+  [self pump];
+}
+
+// Dismisses the browser if it is still presented, and finishes without
+// reading the remaining files, as nobody waits for them anymore.
+- (void)dismiss {
+  UIViewController *browser = self.browser;
+  UIViewController *presenting = browser.presentingViewController;
+  if (presenting && !browser.isBeingDismissed) {
+    [presenting dismissViewControllerAnimated:YES completion:nil];
+    [self sendEvent:(RawPickEvent){.kind = PickEventDismissed, .total = -1}];
+  }
+  [self finish];
+}
+
 - (void)documentPicker:(UIDocumentPickerViewController *)controller
     didPickDocumentsAtURLs:(NSArray<NSURL *> *)urls {
-  NSFileCoordinator *coordinator =
-      [[NSFileCoordinator alloc] initWithFilePresenter:nil];
-  for (NSURL *url in urls) {
-    if ([url startAccessingSecurityScopedResource]) {
-      [coordinator
-          coordinateReadingItemAtURL:url
-                             options:NSFileCoordinatorReadingWithoutChanges
-                               error:nil
-                          byAccessor:^(NSURL *newUrl) {
-                            NSData *data = [NSData
-                                dataWithContentsOfURL:newUrl
-                                              options:NSDataReadingMappedIfSafe
-                                                error:nil];
-                            self.closure(data);
-                          }];
-      [url stopAccessingSecurityScopedResource];
+  if (self.finished) {
+    return;
+  }
+  [self sendEvent:(RawPickEvent){.kind = PickEventDismissed, .total = -1}];
+  if (self.exporting) {
+    for (NSURL *url in urls) {
+      const char *str = [[url absoluteString] UTF8String];
+      self.closure(PickMessageExported, str, strlen(str));
     }
+    [self finish];
+    return;
+  }
+  if (urls.count > self.maxSelection) {
+    urls = [urls subarrayWithRange:NSMakeRange(0, self.maxSelection)];
   }
-  self.closure(nil);
+  self.urls = urls;
+  [self pump];
 }
 
 - (void)documentPickerWasCancelled:
     (UIDocumentPickerViewController *)controller {
-  self.closure(nil);
+  if (self.finished) {
+    return;
+  }
+  [self sendEvent:(RawPickEvent){.kind = PickEventDismissed, .total = -1}];
+  self.closure(PickMessageCancelled, NULL, 0);
+  [self finish];
 }
 @end
 
-FilePickerDelegate *
-show_browser(UIViewController *__unsafe_unretained controller,
-             const char *const *const extensions, const size_t types_len,
-             const bool allow_multiple,
-             void (*closure)(const void *, size_t, void *),
-             void *closure_data) {
+// The delegate could be created on any thread, and is returned retained to be
+// released by the Rust side on the main queue.
+NS_RETURNS_RETAINED FilePickerDelegate *
+picker_create_delegate(void (*closure)(PickMessageKind, const void *, size_t,
+                                       void *),
+                       void *closure_data) {
+  return [[FilePickerDelegate alloc]
+      initWithClosure:^(PickMessageKind kind, const void *data, size_t len) {
+        closure(kind, data, len, closure_data);
+      }];
+}
+
+// The root view controller of the key window of the foreground scene.
+static UIViewController *key_window_root(void) {
+  for (UIScene *scene in UIApplication.sharedApplication.connectedScenes) {
+    if (scene.activationState != UISceneActivationStateForegroundActive ||
+        ![scene isKindOfClass:[UIWindowScene class]]) {
+      continue;
+    }
+    for (UIWindow *window in ((UIWindowScene *)scene).windows) {
+      if (window.isKeyWindow) {
+        return window.rootViewController;
+      }
+    }
+  }
+  return nil;
+}
+
+// The top-most view controller presented over the view controller or view, or
+// else over the key window, so that the picker isn't ignored because another
+// modal is up.
+static UIViewController *top_controller(id base) {
+  UIViewController *controller = nil;
+  if ([base isKindOfClass:[UIViewController class]]) {
+    controller = base;
+  } else if ([base isKindOfClass:[UIView class]]) {
+    for (UIResponder *r = base; r && !controller; r = r.nextResponder) {
+      if ([r isKindOfClass:[UIViewController class]]) {
+        controller = (UIViewController *)r;
+      }
+    }
+    if (!controller) {
+      controller = ((UIView *)base).window.rootViewController;
+    }
+  } else {
+    controller = key_window_root();
+  }
+  while (controller.presentedViewController &&
+         !controller.presentedViewController.isBeingDismissed) {
+    controller = controller.presentedViewController;
+  }
+  return controller;
+}
+
+// Fail the pick if there's nothing to present the picker.
+static UIViewController *
+resolve_presenter(id base, FilePickerDelegate *__unsafe_unretained delegate) {
+  UIViewController *controller = top_controller(base);
+  if (!controller) {
+    send_error(delegate.closure, PickMessageFailed, PickErrorNoPresenter, nil);
+    [delegate finish];
+  }
+  return controller;
+}
+
+// Must be called on the main thread.
+void show_browser(id base,
+                  const PickerOptions *options,
+                  FilePickerDelegate *__unsafe_unretained delegate) {
+  UIViewController *controller = resolve_presenter(base, delegate);
+  if (!controller) {
+    return;
+  }
+  PickClosure c = delegate.closure;
+
   NSMutableArray<UTType *> *types =
-      [NSMutableArray arrayWithCapacity:types_len];
-  for (size_t i = 0; i < types_len; i++) {
-    NSString *ex = [NSString stringWithUTF8String:extensions[i]];
+      [NSMutableArray arrayWithCapacity:options->types_len];
+  for (size_t i = 0; i < options->types_len; i++) {
+    NSString *ex = [NSString stringWithUTF8String:options->extensions[i]];
     UTType *type = [UTType typeWithFilenameExtension:ex];
+    if (!type) {
+      RawPickError raw = {
+          .kind = PickErrorUnsupportedType,
+          .domain = NULL,
+          .code = 0,
+          .description = options->extensions[i],
+      };
+      c(PickMessageFailed, &raw, sizeof(raw));
+      [delegate finish];
+      return;
+    }
+    [types addObject:type];
+  }
+  for (size_t i = 0; i < options->identifiers_len; i++) {
+    NSString *identifier =
+        [NSString stringWithUTF8String:options->identifiers[i]];
+    UTType *type = [UTType typeWithIdentifier:identifier];
+    if (!type) {
+      RawPickError raw = {
+          .kind = PickErrorUnsupportedType,
+          .domain = NULL,
+          .code = 0,
+          .description = options->identifiers[i],
+      };
+      c(PickMessageFailed, &raw, sizeof(raw));
+      [delegate finish];
+      return;
+    }
     [types addObject:type];
   }
 
+  if (options->folder) {
+    [types setArray:@[ UTTypeFolder ]];
+  }
+
+  // Folders are always opened in place, to be enumerated.
+  bool as_copy = options->as_copy && !options->folder;
   UIDocumentPickerViewController *browser =
-      [[UIDocumentPickerViewController alloc] initForOpeningContentTypes:types];
-  browser.allowsMultipleSelection = allow_multiple ? YES : NO;
-  browser.shouldShowFileExtensions = YES;
-
-  FilePickerDelegate *delegate =
-      [[FilePickerDelegate alloc] initWithClosure:^(NSData *data) {
-        if (data) {
-          closure([data bytes], [data length], closure_data);
-        } else {
-          closure(NULL, 0, closure_data);
-        }
-      }];
+      [[UIDocumentPickerViewController alloc]
+          initForOpeningContentTypes:types
+                              asCopy:as_copy ? YES : NO];
+  browser.allowsMultipleSelection = options->allow_multiple ? YES : NO;
+  browser.shouldShowFileExtensions = options->show_extensions ? YES : NO;
+  browser.modalPresentationStyle =
+      (UIModalPresentationStyle)options->presentation_style;
+  if (options->initial_directory) {
+    browser.directoryURL = [NSURL
+        fileURLWithPath:[NSString
+                            stringWithUTF8String:options->initial_directory]
+            isDirectory:YES];
+  }
+
+  delegate.asCopy = as_copy;
+  delegate.folder = options->folder;
+  delegate.bookmarks = options->bookmarks;
+  delegate.streaming = options->streaming;
+  delegate.maxSelection = options->max_selection;
+  delegate.maxFileSize = options->max_file_size;
+  delegate.byteBudget = options->byte_budget;
+  delegate.concurrentReads = MAX(options->concurrent_reads, (size_t)1);
+  delegate.events = options->events;
+  delegate.browser = browser;
   browser.delegate = delegate;
 
-  [controller presentViewController:browser animated:YES completion:nil];
+  __weak FilePickerDelegate *weakDelegate = delegate;
+  [controller presentViewController:browser
+                           animated:YES
+                         completion:^{
+                           [weakDelegate sendEvent:(RawPickEvent){
+                                                       .kind = PickEventPresented,
+                                                       .total = -1,
+                                                   }];
+                         }];
+}
 
-  return delegate;
+// Must be called on the main thread.
+void picker_request_next(FilePickerDelegate *__unsafe_unretained delegate) {
+  [delegate requestNext];
+}
+
+// Must be called on the main thread.
+void picker_dismiss(FilePickerDelegate *__unsafe_unretained delegate) {
+  [delegate dismiss];
+}
+
+void picker_read_file(const char *path,
+                      void (*closure)(PickMessageKind, const void *, size_t,
+                                      void *),
+                      void *closure_data) {
+  NSURL *url = [NSURL fileURLWithPath:[NSString stringWithUTF8String:path]];
+  PickClosure c = ^(PickMessageKind kind, const void *data, size_t len) {
+    closure(kind, data, len, closure_data);
+  };
+  dispatch_async(dispatch_get_global_queue(QOS_CLASS_USER_INITIATED, 0), ^{
+    read_coordinated(c, url, nil, nil);
+    c(PickMessageFinished, NULL, 0);
+  });
+}
+
+void picker_read_range(const char *path, uint64_t offset, size_t len,
+                       void (*closure)(PickMessageKind, const void *, size_t,
+                                       void *),
+                       void *closure_data) {
+  NSURL *url = [NSURL fileURLWithPath:[NSString stringWithUTF8String:path]];
+  PickClosure c = ^(PickMessageKind kind, const void *data, size_t len) {
+    closure(kind, data, len, closure_data);
+  };
+  dispatch_async(dispatch_get_global_queue(QOS_CLASS_USER_INITIATED, 0), ^{
+    NSFileCoordinator *coordinator =
+        [[NSFileCoordinator alloc] initWithFilePresenter:nil];
+    NSError *coordinationError = nil;
+    __block bool accessed = false;
+    [coordinator
+        coordinateReadingItemAtURL:url
+                           options:NSFileCoordinatorReadingWithoutChanges
+                             error:&coordinationError
+                        byAccessor:^(NSURL *newUrl) {
+                          accessed = true;
+                          NSError *readError = nil;
+                          NSFileHandle *handle =
+                              [NSFileHandle fileHandleForReadingFromURL:newUrl
+                                                                  error:&readError];
+                          NSData *chunk = nil;
+                          if (handle && [handle seekToOffset:offset
+                                                       error:&readError]) {
+                            chunk = [handle readDataUpToLength:len
+                                                         error:&readError];
+                          }
+                          [handle closeAndReturnError:nil];
+                          if (chunk) {
+                            c(PickMessageChunk, [chunk bytes], [chunk length]);
+                          } else {
+                            send_error(c, PickMessageFileError,
+                                       PickErrorReadFailed, readError);
+                          }
+                        }];
+    if (!accessed) {
+      send_error(c, PickMessageFileError, PickErrorCoordinationFailed,
+                 coordinationError);
+    }
+    c(PickMessageFinished, NULL, 0);
+  });
+}
+
+// Reads the metadata of `path` with coordination, without its content.
+void picker_stat(const char *path,
+                 void (*closure)(PickMessageKind, const void *, size_t, void *),
+                 void *closure_data) {
+  NSURL *url = [NSURL fileURLWithPath:[NSString stringWithUTF8String:path]];
+  PickClosure c = ^(PickMessageKind kind, const void *data, size_t len) {
+    closure(kind, data, len, closure_data);
+  };
+  dispatch_async(dispatch_get_global_queue(QOS_CLASS_USER_INITIATED, 0), ^{
+    NSFileCoordinator *coordinator =
+        [[NSFileCoordinator alloc] initWithFilePresenter:nil];
+    NSError *coordinationError = nil;
+    __block bool accessed = false;
+    [coordinator
+        coordinateReadingItemAtURL:url
+                           options:NSFileCoordinatorReadingWithoutChanges |
+                                   NSFileCoordinatorReadingImmediatelyAvailableMetadataOnly
+                             error:&coordinationError
+                        byAccessor:^(NSURL *newUrl) {
+                          accessed = true;
+                          send_file(c, url, newUrl, nil, nil);
+                        }];
+    if (!accessed) {
+      send_error(c, PickMessageFileError, PickErrorCoordinationFailed,
+                 coordinationError);
+    }
+    c(PickMessageFinished, NULL, 0);
+  });
+}
+
+// Changes the item at the path of `operation` with coordination, reporting the
+// resulting file without content, except when deleting.
+void picker_write(const RawWriteOperation *operation,
+                  void (*closure)(PickMessageKind, const void *, size_t,
+                                  void *),
+                  void *closure_data) {
+  WriteOperationKind kind = operation->kind;
+  NSURL *url = [NSURL
+      fileURLWithPath:[NSString stringWithUTF8String:operation->path]];
+  NSURL *destination =
+      operation->destination
+          ? [NSURL fileURLWithPath:[NSString stringWithUTF8String:
+                                                 operation->destination]]
+          : nil;
+  NSData *data = [NSData dataWithBytes:operation->data length:operation->len];
+  PickClosure c = ^(PickMessageKind kind, const void *data, size_t len) {
+    closure(kind, data, len, closure_data);
+  };
+  dispatch_async(dispatch_get_global_queue(QOS_CLASS_USER_INITIATED, 0), ^{
+    NSFileCoordinator *coordinator =
+        [[NSFileCoordinator alloc] initWithFilePresenter:nil];
+    NSFileManager *manager = [NSFileManager defaultManager];
+    NSError *coordinationError = nil;
+    __block bool accessed = false;
+    __block NSError *writeError = nil;
+    __block NSURL *result = nil;
+    if (kind == WriteOperationMove) {
+      [coordinator
+          coordinateWritingItemAtURL:url
+                             options:NSFileCoordinatorWritingForMoving
+                    writingItemAtURL:destination
+                             options:NSFileCoordinatorWritingForReplacing
+                               error:&coordinationError
+                          byAccessor:^(NSURL *from, NSURL *to) {
+                            accessed = true;
+                            NSError *error = nil;
+                            if ([manager moveItemAtURL:from
+                                                 toURL:to
+                                                 error:&error]) {
+                              [coordinator itemAtURL:from didMoveToURL:to];
+                              result = to;
+                            } else {
+                              writeError = error;
+                            }
+                          }];
+    } else {
+      NSFileCoordinatorWritingOptions options =
+          kind == WriteOperationDelete  ? NSFileCoordinatorWritingForDeleting
+          : kind == WriteOperationWrite ? 0
+                                        : NSFileCoordinatorWritingForReplacing;
+      [coordinator
+          coordinateWritingItemAtURL:url
+                             options:options
+                               error:&coordinationError
+                          byAccessor:^(NSURL *newUrl) {
+                            accessed = true;
+                            NSError *error = nil;
+                            BOOL done = NO;
+                            switch (kind) {
+                            case WriteOperationDelete:
+                              done = [manager removeItemAtURL:newUrl
+                                                        error:&error];
+                              break;
+                            case WriteOperationCreate:
+                              done = [data
+                                  writeToURL:newUrl
+                                     options:NSDataWritingWithoutOverwriting
+                                       error:&error];
+                              break;
+                            case WriteOperationReplace:
+                              done = [data writeToURL:newUrl
+                                              options:NSDataWritingAtomic
+                                                error:&error];
+                              break;
+                            default:
+                              done = [data writeToURL:newUrl
+                                              options:0
+                                                error:&error];
+                              break;
+                            }
+                            if (done) {
+                              result = newUrl;
+                            } else {
+                              writeError = error;
+                            }
+                          }];
+    }
+    if (!accessed) {
+      send_error(c, PickMessageFileError, PickErrorCoordinationFailed,
+                 coordinationError);
+    } else if (!result) {
+      send_error(c, PickMessageFileError, PickErrorWriteFailed, writeError);
+    } else if (kind != WriteOperationDelete) {
+      send_file(c, destination ?: url, result, nil, nil);
+    }
+    c(PickMessageFinished, NULL, 0);
+  });
+}
+
+void picker_resolve_bookmark(const void *bookmark, size_t bookmark_len,
+                             void (*closure)(PickMessageKind, const void *,
+                                             size_t, void *),
+                             void *closure_data) {
+  NSData *bookmarkData = [NSData dataWithBytes:bookmark length:bookmark_len];
+  PickClosure c = ^(PickMessageKind kind, const void *data, size_t len) {
+    closure(kind, data, len, closure_data);
+  };
+  dispatch_async(dispatch_get_global_queue(QOS_CLASS_USER_INITIATED, 0), ^{
+    BOOL stale = NO;
+    NSError *error = nil;
+    NSURL *url = [NSURL URLByResolvingBookmarkData:bookmarkData
+                                           options:0
+                                     relativeToURL:nil
+                               bookmarkDataIsStale:&stale
+                                             error:&error];
+    if (!url) {
+      send_error(c, PickMessageFileError, PickErrorBookmarkFailed, error);
+    } else if (![url startAccessingSecurityScopedResource]) {
+      send_error(c, PickMessageFileError, PickErrorAccessDenied, nil);
+    } else {
+      NSData *refreshed = stale ? create_bookmark(url) : bookmarkData;
+      read_coordinated(c, url, refreshed ?: bookmarkData, nil);
+      [url stopAccessingSecurityScopedResource];
+    }
+    c(PickMessageFinished, NULL, 0);
+  });
+}
+
+// Must be called on the main thread.
+void show_exporter(id base, const char *const *paths, size_t paths_len,
+                   FilePickerDelegate *__unsafe_unretained delegate) {
+  UIViewController *controller = resolve_presenter(base, delegate);
+  if (!controller) {
+    return;
+  }
+  NSMutableArray<NSURL *> *urls = [NSMutableArray arrayWithCapacity:paths_len];
+  for (size_t i = 0; i < paths_len; i++) {
+    NSString *path = [NSString stringWithUTF8String:paths[i]];
+    [urls addObject:[NSURL fileURLWithPath:path]];
+  }
+
+  UIDocumentPickerViewController *browser =
+      [[UIDocumentPickerViewController alloc] initForExportingURLs:urls
+                                                            asCopy:YES];
+
+  delegate.exporting = true;
+  delegate.browser = browser;
+  browser.delegate = delegate;
+
+  [controller presentViewController:browser animated:YES completion:nil];
 }
@@ -0,0 +1,1010 @@
+use super::{
+    ExportRequest, PickMessageKind, PickRequest, PickerBackend, Presentation, PresentationHandle,
+    RawCallback, RawPickError, RawPickErrorKind, RawPickEvent, RawPickEventKind, RawPickedFile,
+    WriteOperation,
+};
+use crate::{
+    file::system_time_to_secs, Bookmark, FileMetadata, NativeError, PickError, PickOptions,
+};
+use std::{
+    collections::{HashMap, VecDeque},
+    ffi::{c_void, CString},
+    fs,
+    io::{self, Read, Seek, Write},
+    mem::size_of,
+    path::{Path, PathBuf},
+    ptr::null,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+/// An error scripted in the fake, which must be one the native side reports.
+///
+/// [`PickError::Cancelled`], [`PickError::TimedOut`], [`PickError::Io`],
+/// [`PickError::TypeMismatch`] and [`PickError::InvalidPattern`] are found on
+/// the Rust side, and so are rejected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FakeError(PickError);
+
+impl FakeError {
+    /// Check that `error` could be reported by the native side, or else
+    /// return it back.
+    pub fn new(error: PickError) -> Result<Self, PickError> {
+        match error {
+            PickError::Cancelled
+            | PickError::TimedOut
+            | PickError::Io { .. }
+            | PickError::TypeMismatch { .. }
+            | PickError::InvalidPattern(_) => Err(error),
+            error => Ok(Self(error)),
+        }
+    }
+
+    /// The error.
+    pub fn error(&self) -> &PickError {
+        &self.0
+    }
+}
+
+impl TryFrom<PickError> for FakeError {
+    type Error = PickError;
+
+    fn try_from(error: PickError) -> Result<Self, PickError> {
+        Self::new(error)
+    }
+}
+
+/// A scripted file of a [`FakeResponse`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FakeFile {
+    /// The file is read successfully with the metadata and content.
+    Contents(FileMetadata, Vec<u8>),
+    /// The file couldn't be read.
+    Failed(FakeError),
+}
+
+impl FakeFile {
+    /// A file read successfully with `contents`.
+    pub fn contents(contents: impl Into<Vec<u8>>) -> Self {
+        Self::named("untitled", contents)
+    }
+
+    /// A file named `name` read successfully with `contents`.
+    pub fn named(name: impl Into<String>, contents: impl Into<Vec<u8>>) -> Self {
+        let name = name.into();
+        let contents = contents.into();
+        let metadata = FileMetadata {
+            url: format!("file:///private/var/mobile/{name}"),
+            size: Some(contents.len() as u64),
+            ..FileMetadata::new(name)
+        };
+        Self::Contents(metadata, contents)
+    }
+
+    /// A folder at the local `path`, picked when [`PickOptions::folder`] is
+    /// set.
+    pub fn folder(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let metadata = FileMetadata {
+            url: format!("file://{}/", path.display()),
+            path: Some(path),
+            ..FileMetadata::new(name)
+        };
+        Self::Contents(metadata, vec![])
+    }
+}
+
+fn native_error(e: io::Error) -> NativeError {
+    NativeError {
+        domain: "NSPOSIXErrorDomain".to_string(),
+        code: e.raw_os_error().unwrap_or_default() as isize,
+        description: e.to_string(),
+    }
+}
+
+fn posix_error(e: io::Error) -> FakeError {
+    FakeError(PickError::ReadFailed(native_error(e)))
+}
+
+fn already_exists(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        format!("{} already exists", path.display()),
+    )
+}
+
+fn local_metadata(path: &Path) -> io::Result<FileMetadata> {
+    let m = fs::metadata(path)?;
+    Ok(FileMetadata {
+        url: format!("file://{}", path.display()),
+        path: Some(path.to_path_buf()),
+        size: Some(m.len()),
+        created: m.created().ok(),
+        modified: m.modified().ok(),
+        ..FileMetadata::new(
+            path.file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+        )
+    })
+}
+
+#[derive(Debug, Clone)]
+enum FakeOutcome {
+    Picked(Vec<FakeFile>),
+    Exported(Vec<String>),
+    Cancelled,
+    Failed(FakeError),
+}
+
+/// A scripted answer of [`FakeBackend`] to one presentation.
+#[derive(Debug, Clone)]
+pub struct FakeResponse {
+    outcome: FakeOutcome,
+    delay: Option<Duration>,
+}
+
+impl FakeResponse {
+    /// The user picks `files`.
+    pub fn picked(files: impl IntoIterator<Item = FakeFile>) -> Self {
+        Self {
+            outcome: FakeOutcome::Picked(files.into_iter().collect()),
+            delay: None,
+        }
+    }
+
+    /// The user exports the files to the destination `urls`.
+    ///
+    /// It only answers an export picker, and a file picker taking it is
+    /// cancelled, and vice versa for [`FakeResponse::picked`].
+    pub fn exported<I>(urls: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        Self {
+            outcome: FakeOutcome::Exported(urls.into_iter().map(Into::into).collect()),
+            delay: None,
+        }
+    }
+
+    /// The user cancels the picker.
+    pub fn cancelled() -> Self {
+        Self {
+            outcome: FakeOutcome::Cancelled,
+            delay: None,
+        }
+    }
+
+    /// The picker couldn't be presented.
+    pub fn failed(error: FakeError) -> Self {
+        Self {
+            outcome: FakeOutcome::Failed(error),
+            delay: None,
+        }
+    }
+
+    /// Deliver the callbacks on another thread, each after `delay`.
+    pub fn delayed(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+}
+
+/// An export presentation recorded by [`FakeBackend`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FakeExport {
+    /// The exported local files.
+    pub paths: Vec<PathBuf>,
+    /// The contents of the files when presenting.
+    pub contents: Vec<Vec<u8>>,
+}
+
+#[derive(Debug, Clone)]
+struct FakeBookmark {
+    file: FakeFile,
+    refreshed: Option<Bookmark>,
+}
+
+#[derive(Debug, Default)]
+struct FakeState {
+    responses: VecDeque<FakeResponse>,
+    requests: Vec<PickOptions>,
+    exports: Vec<FakeExport>,
+    bookmarks: HashMap<Bookmark, FakeBookmark>,
+    streamed: HashMap<PathBuf, (FileMetadata, Vec<u8>)>,
+    dismissals: usize,
+}
+
+/// A scriptable backend which works on any host.
+///
+/// Every presentation takes the next queued [`FakeResponse`] and calls back
+/// the same way `native/picker.m` does, delivering one file per request. If no
+/// response is queued, the picker is cancelled.
+///
+/// If [`PickOptions::streaming`] is set, the files are reported without
+/// content, and [`PickerBackend::read_file`], [`PickerBackend::read_range`]
+/// and [`PickerBackend::stat`] read their scripted contents, or any local
+/// file, which [`PickerBackend::write`] changes.
+///
+/// Dropping a presentation before its picker finishes dismisses it, as
+/// counted by [`FakeBackend::dismissals`].
+///
+/// If [`PickOptions::events`] is set, the picker is presented at once and
+/// dismissed after the delay, and every file read at once is reported with
+/// one step of progress when it is delivered.
+///
+/// The limits of [`PickOptions::max_file_size`] and
+/// [`PickOptions::byte_budget`] are checked against [`FileMetadata::size`].
+///
+/// If [`PickOptions::bookmarks`] is set, every picked file gets a bookmark
+/// which resolves to the same file, and unknown bookmarks fail with
+/// [`PickError::BookmarkFailed`].
+#[derive(Debug, Default, Clone)]
+pub struct FakeBackend {
+    state: Arc<Mutex<FakeState>>,
+}
+
+impl FakeBackend {
+    /// Create a backend without any queued response.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue a response for the next presentation.
+    pub fn push_response(&self, response: FakeResponse) -> &Self {
+        self.state.lock().unwrap().responses.push_back(response);
+        self
+    }
+
+    /// The options of the presentations recorded so far.
+    pub fn requests(&self) -> Vec<PickOptions> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    /// The export presentations recorded so far.
+    pub fn exports(&self) -> Vec<FakeExport> {
+        self.state.lock().unwrap().exports.clone()
+    }
+
+    /// The count of pickers dismissed before they finished.
+    pub fn dismissals(&self) -> usize {
+        self.state.lock().unwrap().dismissals
+    }
+
+    /// Drop the contents kept for the picked file at `path`, so that later
+    /// reads of it fail like after the access to it is lost.
+    pub fn revoke(&self, path: &Path) -> &Self {
+        self.state.lock().unwrap().streamed.remove(path);
+        self
+    }
+
+    /// Make `bookmark` resolve to `file`.
+    pub fn add_bookmark(&self, bookmark: Bookmark, file: FakeFile) -> &Self {
+        let bookmark_file = FakeBookmark {
+            file,
+            refreshed: None,
+        };
+        self.state
+            .lock()
+            .unwrap()
+            .bookmarks
+            .insert(bookmark, bookmark_file);
+        self
+    }
+
+    /// Make `bookmark` resolve to `file` as stale, reporting `refreshed`
+    /// instead, which then resolves to `file` too.
+    pub fn add_stale_bookmark(
+        &self,
+        bookmark: Bookmark,
+        refreshed: Bookmark,
+        file: FakeFile,
+    ) -> &Self {
+        self.add_bookmark(refreshed.clone(), file.clone());
+        let bookmark_file = FakeBookmark {
+            file,
+            refreshed: Some(refreshed),
+        };
+        self.state
+            .lock()
+            .unwrap()
+            .bookmarks
+            .insert(bookmark, bookmark_file);
+        self
+    }
+
+    /// Remove every bookmark, as if the documents are gone.
+    pub fn clear_bookmarks(&self) {
+        self.state.lock().unwrap().bookmarks.clear();
+    }
+}
+
+/// Hands over the contents like the native side hands over an `NSData`.
+unsafe extern "C" fn release_contents(owner: *mut c_void) {
+    drop(Box::from_raw(owner as *mut Vec<u8>));
+}
+
+fn with_raw_file<T>(
+    metadata: &FileMetadata,
+    contents: &[u8],
+    f: impl FnOnce(&RawPickedFile) -> T,
+) -> T {
+    let cstring = |s: &str| CString::new(s).unwrap();
+    let name = cstring(&metadata.name);
+    let url = cstring(&metadata.url);
+    let path = metadata
+        .path
+        .as_ref()
+        .map(|p| CString::new(p.as_os_str().as_encoded_bytes()).unwrap());
+    let content_type = metadata.content_type.as_deref().map(cstring);
+    let provider = metadata.provider.as_deref().map(cstring);
+    let owner = Box::into_raw(Box::new(contents.to_vec()));
+    f(&RawPickedFile {
+        data: unsafe { (*owner).as_ptr().cast() },
+        len: contents.len(),
+        owner: owner.cast(),
+        release: Some(release_contents),
+        name: name.as_ptr(),
+        url: url.as_ptr(),
+        path: path.as_ref().map_or(null(), |s| s.as_ptr()),
+        bookmark: metadata
+            .bookmark
+            .as_ref()
+            .map_or(null(), |b| b.as_bytes().as_ptr().cast()),
+        bookmark_len: metadata.bookmark.as_ref().map_or(0, |b| b.as_bytes().len()),
+        content_type: content_type.as_ref().map_or(null(), |s| s.as_ptr()),
+        size: metadata.size.map_or(-1, |s| s as i64),
+        created: metadata.created.map_or(f64::NAN, system_time_to_secs),
+        modified: metadata.modified.map_or(f64::NAN, system_time_to_secs),
+        provider: provider.as_ref().map_or(null(), |s| s.as_ptr()),
+    })
+}
+
+fn with_raw_error<T>(error: &FakeError, f: impl FnOnce(&RawPickError) -> T) -> T {
+    let error = &error.0;
+    let (kind, native) = match error {
+        PickError::AccessDenied => (RawPickErrorKind::AccessDenied, None),
+        PickError::CoordinationFailed(e) => (RawPickErrorKind::CoordinationFailed, Some(e)),
+        PickError::ReadFailed(e) => (RawPickErrorKind::ReadFailed, Some(e)),
+        PickError::WriteFailed(e) => (RawPickErrorKind::WriteFailed, Some(e)),
+        PickError::UnsupportedType(t) => {
+            let t = CString::new(t.as_str()).unwrap();
+            return f(&RawPickError {
+                kind: RawPickErrorKind::UnsupportedType,
+                domain: null(),
+                code: 0,
+                description: t.as_ptr(),
+                size: 0,
+                limit: 0,
+            });
+        }
+        PickError::FileTooLarge { name, size, limit }
+        | PickError::BudgetExceeded {
+            name,
+            size,
+            remaining: limit,
+        } => {
+            let name = CString::new(name.as_str()).unwrap();
+            let kind = match error {
+                PickError::FileTooLarge { .. } => RawPickErrorKind::FileTooLarge,
+                _ => RawPickErrorKind::BudgetExceeded,
+            };
+            return f(&RawPickError {
+                kind,
+                domain: null(),
+                code: 0,
+                description: name.as_ptr(),
+                size: *size,
+                limit: *limit,
+            });
+        }
+        PickError::UnsupportedPlatform => (RawPickErrorKind::UnsupportedPlatform, None),
+        PickError::NoPresenter => (RawPickErrorKind::NoPresenter, None),
+        PickError::BookmarkFailed(e) => (RawPickErrorKind::BookmarkFailed, Some(e)),
+        PickError::Busy { current } => {
+            return f(&RawPickError {
+                kind: RawPickErrorKind::Busy,
+                domain: null(),
+                code: 0,
+                description: null(),
+                size: current.get(),
+                limit: 0,
+            });
+        }
+        PickError::Cancelled
+        | PickError::TimedOut
+        | PickError::Io { .. }
+        | PickError::TypeMismatch { .. }
+        | PickError::InvalidPattern(_) => unreachable!("rejected by FakeError::new"),
+    };
+    let domain = native.map(|e| CString::new(e.domain.as_str()).unwrap());
+    let description = native.map(|e| CString::new(e.description.as_str()).unwrap());
+    f(&RawPickError {
+        kind,
+        domain: domain.as_ref().map_or(null(), |s| s.as_ptr()),
+        code: native.map_or(0, |e| e.code),
+        description: description.as_ref().map_or(null(), |s| s.as_ptr()),
+        size: 0,
+        limit: 0,
+    })
+}
+
+/// Fail the files beyond the size limits, like the native side does from the
+/// metadata before reading.
+fn limit_sizes(files: &mut [FakeFile], options: &PickOptions) {
+    let mut remaining = options.byte_budget;
+    for file in files {
+        let FakeFile::Contents(metadata, _) = file else {
+            continue;
+        };
+        let Some(size) = metadata.size else {
+            continue;
+        };
+        let name = metadata.name.clone();
+        if let Some(limit) = options.max_file_size.filter(|&limit| size > limit) {
+            *file = FakeFile::Failed(FakeError(PickError::FileTooLarge { name, size, limit }));
+        } else if let Some(left) = remaining.as_mut() {
+            if size > *left {
+                *file = FakeFile::Failed(FakeError(PickError::BudgetExceeded {
+                    name,
+                    size,
+                    remaining: *left,
+                }));
+            } else {
+                *left -= size;
+            }
+        }
+    }
+}
+
+struct CallbackData(*mut c_void);
+
+// SAFETY: the callback data is owned by the callback.
+unsafe impl Send for CallbackData {}
+
+struct FakeSession {
+    files: VecDeque<FakeFile>,
+    exported: Vec<String>,
+    requested: usize,
+    callback: RawCallback,
+    callback_data: Option<CallbackData>,
+    // Whether the files are picked, after the delay.
+    picked: bool,
+    events: bool,
+    // Whether the files are read, and so reported with the file events.
+    reads: bool,
+    presented: bool,
+    index: usize,
+}
+
+impl FakeSession {
+    fn new(callback: RawCallback, callback_data: *mut c_void) -> Self {
+        Self {
+            files: VecDeque::new(),
+            exported: vec![],
+            requested: 0,
+            callback,
+            callback_data: Some(CallbackData(callback_data)),
+            picked: false,
+            events: false,
+            reads: false,
+            presented: false,
+            index: 0,
+        }
+    }
+
+    unsafe fn event(&self, kind: RawPickEventKind, name: Option<&str>, size: Option<u64>) {
+        let Some(CallbackData(data)) = self.callback_data else {
+            return;
+        };
+        if !self.events {
+            return;
+        }
+        let name = name.map(|name| CString::new(name).unwrap());
+        let event = RawPickEvent {
+            kind,
+            index: self.index,
+            name: name.as_ref().map_or(null(), |s| s.as_ptr()),
+            completed: size.unwrap_or_default(),
+            total: size.map_or(-1, |s| s as i64),
+        };
+        (self.callback)(
+            PickMessageKind::Event,
+            (&event as *const RawPickEvent).cast(),
+            size_of::<RawPickEvent>(),
+            data,
+        );
+    }
+
+    unsafe fn present(&mut self) {
+        self.presented = true;
+        self.event(RawPickEventKind::Presented, None, None);
+    }
+
+    unsafe fn dismissed(&mut self) {
+        if self.presented {
+            self.presented = false;
+            self.event(RawPickEventKind::Dismissed, None, None);
+        }
+    }
+
+    /// Report a file as read at once, in one step of progress.
+    unsafe fn read_events(&self, file: &FakeFile) {
+        if !self.reads {
+            return;
+        }
+        let (name, size) = match file {
+            FakeFile::Contents(metadata, _) => (metadata.name.as_str(), metadata.size),
+            FakeFile::Failed(_) => ("", None),
+        };
+        self.event(RawPickEventKind::FileStarted, Some(name), None);
+        if size.is_some() {
+            self.event(RawPickEventKind::Progress, None, size);
+        }
+        self.event(RawPickEventKind::FileFinished, None, None);
+    }
+
+    /// Deliver the requested files, and finish after the last one, like the
+    /// `FilePickerDelegate` does.
+    unsafe fn pump(&mut self) {
+        let Some(CallbackData(data)) = self.callback_data else {
+            return;
+        };
+        if !self.picked {
+            return;
+        }
+        while self.requested > 0 {
+            let Some(file) = self.files.pop_front() else {
+                break;
+            };
+            self.requested -= 1;
+            self.read_events(&file);
+            self.index += 1;
+            match file {
+                FakeFile::Contents(metadata, contents) => {
+                    with_raw_file(&metadata, &contents, |file| {
+                        (self.callback)(
+                            PickMessageKind::File,
+                            (file as *const RawPickedFile).cast(),
+                            size_of::<RawPickedFile>(),
+                            data,
+                        )
+                    })
+                }
+                FakeFile::Failed(error) => with_raw_error(&error, |error| {
+                    (self.callback)(
+                        PickMessageKind::FileError,
+                        (error as *const RawPickError).cast(),
+                        size_of::<RawPickError>(),
+                        data,
+                    )
+                }),
+            }
+        }
+        if self.files.is_empty() {
+            self.callback_data = None;
+            (self.callback)(PickMessageKind::Finished, null(), 0, data);
+        }
+    }
+
+    unsafe fn export(&mut self) {
+        if let Some(CallbackData(data)) = self.callback_data.take() {
+            for url in self.exported.drain(..) {
+                let url = CString::new(url).unwrap();
+                (self.callback)(
+                    PickMessageKind::Exported,
+                    url.as_ptr().cast(),
+                    url.as_bytes().len(),
+                    data,
+                );
+            }
+            (self.callback)(PickMessageKind::Finished, null(), 0, data);
+        }
+    }
+
+    unsafe fn cancel(&mut self) {
+        if let Some(CallbackData(data)) = self.callback_data.take() {
+            (self.callback)(PickMessageKind::Cancelled, null(), 0, data);
+            (self.callback)(PickMessageKind::Finished, null(), 0, data);
+        }
+    }
+
+    unsafe fn fail(&mut self, error: &FakeError) {
+        if let Some(CallbackData(data)) = self.callback_data.take() {
+            with_raw_error(error, |error| {
+                (self.callback)(
+                    PickMessageKind::Failed,
+                    (error as *const RawPickError).cast(),
+                    size_of::<RawPickError>(),
+                    data,
+                )
+            });
+            (self.callback)(PickMessageKind::Finished, null(), 0, data);
+        }
+    }
+}
+
+struct FakeHandle {
+    session: Arc<Mutex<FakeSession>>,
+    delay: Option<Duration>,
+    state: Arc<Mutex<FakeState>>,
+}
+
+impl FakeHandle {
+    fn after_delay(&self, f: impl FnOnce(&mut FakeSession) + Send + 'static) {
+        let session = self.session.clone();
+        match self.delay {
+            Some(delay) => {
+                thread::spawn(move || {
+                    thread::sleep(delay);
+                    f(&mut session.lock().unwrap());
+                });
+            }
+            None => f(&mut session.lock().unwrap()),
+        }
+    }
+}
+
+impl PresentationHandle for FakeHandle {
+    fn request_next(&self) {
+        self.after_delay(|session| {
+            session.requested += 1;
+            unsafe { session.pump() }
+        });
+    }
+
+    fn dismiss(&self) {
+        let mut session = self.session.lock().unwrap();
+        if let Some(CallbackData(data)) = session.callback_data {
+            unsafe { session.dismissed() };
+            session.callback_data = None;
+            session.files.clear();
+            self.state.lock().unwrap().dismissals += 1;
+            unsafe { (session.callback)(PickMessageKind::Finished, null(), 0, data) };
+        }
+    }
+}
+
+impl FakeBackend {
+    fn next_response(&self) -> FakeResponse {
+        self.state
+            .lock()
+            .unwrap()
+            .responses
+            .pop_front()
+            .unwrap_or_else(FakeResponse::cancelled)
+    }
+
+    /// Give `file` a bookmark resolving to it, like the native side does when
+    /// bookmarks are requested.
+    fn bookmark(&self, file: &mut FakeFile) {
+        if let FakeFile::Contents(metadata, _) = file {
+            let bookmark = metadata
+                .bookmark
+                .get_or_insert_with(|| Bookmark::from_bytes(format!("bookmark:{}", metadata.url)))
+                .clone();
+            self.add_bookmark(bookmark, file.clone());
+        }
+    }
+
+    /// Keep the contents of `file` to be read later, like the native side
+    /// keeps the access to it.
+    fn stream(&self, file: &mut FakeFile) {
+        if let FakeFile::Contents(metadata, contents) = file {
+            let path = metadata
+                .path
+                .get_or_insert_with(|| PathBuf::from(metadata.url.trim_start_matches("file://")))
+                .clone();
+            let contents = std::mem::take(contents);
+            let streamed = (metadata.clone(), contents);
+            self.state.lock().unwrap().streamed.insert(path, streamed);
+        }
+    }
+
+    /// Change a scripted file kept by [`FakeBackend::stream`], or else a local
+    /// file, returning the metadata of the resulting file.
+    fn apply(&self, operation: &WriteOperation<'_>) -> io::Result<Option<FileMetadata>> {
+        let streamed = &mut self.state.lock().unwrap().streamed;
+        match *operation {
+            WriteOperation::Write { path, data } | WriteOperation::Replace { path, data } => {
+                if let Some((metadata, contents)) = streamed.get_mut(path) {
+                    *contents = data.to_vec();
+                    metadata.size = Some(data.len() as u64);
+                    return Ok(Some(metadata.clone()));
+                }
+                if let WriteOperation::Replace { .. } = operation {
+                    let mut name = path.file_name().unwrap_or_default().to_os_string();
+                    name.push(".replacing");
+                    let temp = path.with_file_name(name);
+                    fs::write(&temp, data)?;
+                    fs::rename(&temp, path)?;
+                } else {
+                    fs::write(path, data)?;
+                }
+                local_metadata(path).map(Some)
+            }
+            WriteOperation::Create { path, data } => {
+                if streamed.contains_key(path) {
+                    return Err(already_exists(path));
+                }
+                fs::OpenOptions::new()
+                    .write(true)
+                    .create_new(true)
+                    .open(path)?
+                    .write_all(data)?;
+                local_metadata(path).map(Some)
+            }
+            WriteOperation::Move { from, to } => {
+                if streamed.contains_key(to) || to.exists() {
+                    return Err(already_exists(to));
+                }
+                if let Some((metadata, contents)) = streamed.remove(from) {
+                    let metadata = FileMetadata {
+                        name: to
+                            .file_name()
+                            .map(|name| name.to_string_lossy().into_owned())
+                            .unwrap_or_default(),
+                        url: format!("file://{}", to.display()),
+                        path: Some(to.to_path_buf()),
+                        ..metadata
+                    };
+                    streamed.insert(to.to_path_buf(), (metadata.clone(), contents));
+                    return Ok(Some(metadata));
+                }
+                fs::rename(from, to)?;
+                local_metadata(to).map(Some)
+            }
+            WriteOperation::Delete { path } => {
+                if streamed.remove(path).is_none() {
+                    if fs::symlink_metadata(path)?.is_dir() {
+                        fs::remove_dir_all(path)?;
+                    } else {
+                        fs::remove_file(path)?;
+                    }
+                }
+                Ok(None)
+            }
+        }
+    }
+
+    /// Report one file without waiting for any request.
+    unsafe fn read_one(&self, file: FakeFile, callback: RawCallback, callback_data: *mut c_void) {
+        let mut session = FakeSession {
+            files: VecDeque::from([file]),
+            requested: 1,
+            picked: true,
+            ..FakeSession::new(callback, callback_data)
+        };
+        session.pump();
+    }
+
+    fn start(
+        &self,
+        mut session: FakeSession,
+        outcome: FakeOutcome,
+        delay: Option<Duration>,
+    ) -> Presentation {
+        if !matches!(outcome, FakeOutcome::Failed(_)) {
+            unsafe { session.present() };
+        }
+        let handle = FakeHandle {
+            session: Arc::new(Mutex::new(session)),
+            delay,
+            state: self.state.clone(),
+        };
+        handle.after_delay(move |session| unsafe {
+            session.dismissed();
+            match outcome {
+                FakeOutcome::Picked(_) => {
+                    session.picked = true;
+                    session.pump();
+                }
+                FakeOutcome::Exported(_) => session.export(),
+                FakeOutcome::Failed(error) => session.fail(&error),
+                FakeOutcome::Cancelled => session.cancel(),
+            }
+        });
+        Presentation::new(handle)
+    }
+}
+
+impl PickerBackend for FakeBackend {
+    unsafe fn present(
+        &self,
+        request: &PickRequest<'_>,
+        callback: RawCallback,
+        callback_data: *mut c_void,
+    ) -> Presentation {
+        self.state
+            .lock()
+            .unwrap()
+            .requests
+            .push(request.options.clone());
+        let response = self.next_response();
+        let (files, outcome) = match response.outcome {
+            FakeOutcome::Picked(mut files) => {
+                let max = if request.options.allow_multiple {
+                    request.options.max_selection.unwrap_or(usize::MAX)
+                } else {
+                    1
+                };
+                files.truncate(max);
+                if request.options.bookmarks {
+                    for file in &mut files {
+                        self.bookmark(file);
+                    }
+                }
+                if request.options.streaming {
+                    for file in &mut files {
+                        self.stream(file);
+                    }
+                } else if !request.options.folder {
+                    limit_sizes(&mut files, request.options);
+                }
+                (files.into(), FakeOutcome::Picked(vec![]))
+            }
+            FakeOutcome::Exported(_) => (VecDeque::new(), FakeOutcome::Cancelled),
+            outcome => (VecDeque::new(), outcome),
+        };
+        let session = FakeSession {
+            files,
+            events: request.options.events,
+            reads: !request.options.streaming && !request.options.folder,
+            ..FakeSession::new(callback, callback_data)
+        };
+        self.start(session, outcome, response.delay)
+    }
+
+    unsafe fn read_file(&self, path: &Path, callback: RawCallback, callback_data: *mut c_void) {
+        let streamed = self.state.lock().unwrap().streamed.get(path).cloned();
+        let file = match streamed {
+            Some(file) => Ok(file),
+            None => local_metadata(path).and_then(|metadata| Ok((metadata, fs::read(path)?))),
+        };
+        let file = match file {
+            Ok((metadata, contents)) => FakeFile::Contents(metadata, contents),
+            Err(e) => FakeFile::Failed(posix_error(e)),
+        };
+        self.read_one(file, callback, callback_data);
+    }
+
+    unsafe fn read_range(
+        &self,
+        path: &Path,
+        offset: u64,
+        len: usize,
+        callback: RawCallback,
+        callback_data: *mut c_void,
+    ) {
+        let streamed = self.state.lock().unwrap().streamed.get(path).cloned();
+        let chunk = match streamed {
+            Some((_, contents)) => {
+                let start =
+                    usize::try_from(offset).map_or(contents.len(), |o| o.min(contents.len()));
+                let end = start.saturating_add(len).min(contents.len());
+                Ok(contents[start..end].to_vec())
+            }
+            None => fs::File::open(path).and_then(|file| {
+                let mut chunk = vec![];
+                let mut file = io::BufReader::new(file);
+                file.seek(io::SeekFrom::Start(offset))?;
+                file.take(len as u64).read_to_end(&mut chunk)?;
+                Ok(chunk)
+            }),
+        };
+        match chunk {
+            Ok(chunk) => callback(
+                PickMessageKind::Chunk,
+                chunk.as_ptr().cast(),
+                chunk.len(),
+                callback_data,
+            ),
+            Err(e) => with_raw_error(&posix_error(e), |error| {
+                callback(
+                    PickMessageKind::FileError,
+                    (error as *const RawPickError).cast(),
+                    size_of::<RawPickError>(),
+                    callback_data,
+                )
+            }),
+        }
+        callback(PickMessageKind::Finished, null(), 0, callback_data);
+    }
+
+    unsafe fn stat(&self, path: &Path, callback: RawCallback, callback_data: *mut c_void) {
+        let streamed = self.state.lock().unwrap().streamed.get(path).cloned();
+        let metadata = match streamed {
+            Some((metadata, _)) => Ok(metadata),
+            None => local_metadata(path),
+        };
+        let file = match metadata {
+            Ok(metadata) => FakeFile::Contents(metadata, vec![]),
+            Err(e) => FakeFile::Failed(posix_error(e)),
+        };
+        self.read_one(file, callback, callback_data);
+    }
+
+    unsafe fn write(
+        &self,
+        operation: &WriteOperation<'_>,
+        callback: RawCallback,
+        callback_data: *mut c_void,
+    ) {
+        match self.apply(operation) {
+            Ok(Some(metadata)) => self.read_one(
+                FakeFile::Contents(metadata, vec![]),
+                callback,
+                callback_data,
+            ),
+            Ok(None) => callback(PickMessageKind::Finished, null(), 0, callback_data),
+            Err(e) => self.read_one(
+                FakeFile::Failed(FakeError(PickError::WriteFailed(native_error(e)))),
+                callback,
+                callback_data,
+            ),
+        }
+    }
+
+    unsafe fn resolve_bookmark(
+        &self,
+        bookmark: &[u8],
+        callback: RawCallback,
+        callback_data: *mut c_void,
+    ) {
+        let bookmark = Bookmark::from_bytes(bookmark);
+        let resolved = self.state.lock().unwrap().bookmarks.get(&bookmark).cloned();
+        let file = match resolved {
+            Some(FakeBookmark {
+                file: FakeFile::Contents(metadata, contents),
+                refreshed,
+            }) => FakeFile::Contents(
+                FileMetadata {
+                    bookmark: Some(refreshed.unwrap_or(bookmark)),
+                    ..metadata
+                },
+                contents,
+            ),
+            Some(FakeBookmark { file, .. }) => file,
+            None => FakeFile::Failed(FakeError(PickError::BookmarkFailed(NativeError {
+                domain: "NSCocoaErrorDomain".to_string(),
+                code: 259,
+                description: "The file couldn't be opened because it isn't in the correct format."
+                    .to_string(),
+            }))),
+        };
+        self.read_one(file, callback, callback_data);
+    }
+
+    unsafe fn present_export(
+        &self,
+        request: &ExportRequest<'_>,
+        callback: RawCallback,
+        callback_data: *mut c_void,
+    ) -> Presentation {
+        self.state.lock().unwrap().exports.push(FakeExport {
+            paths: request.paths.to_vec(),
+            contents: request
+                .paths
+                .iter()
+                .map(|p| fs::read(p).unwrap_or_default())
+                .collect(),
+        });
+        let response = self.next_response();
+        let (exported, outcome) = match response.outcome {
+            FakeOutcome::Exported(urls) => (urls, FakeOutcome::Exported(vec![])),
+            FakeOutcome::Picked(_) => (vec![], FakeOutcome::Cancelled),
+            outcome => (vec![], outcome),
+        };
+        let session = FakeSession {
+            exported,
+            ..FakeSession::new(callback, callback_data)
+        };
+        self.start(session, outcome, response.delay)
+    }
+}
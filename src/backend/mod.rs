@@ -0,0 +1,609 @@
+//! Backends which present the document picker.
+//!
+//! The public functions of this crate don't talk to UIKit directly. They hand a
+//! [`PickRequest`] to a [`PickerBackend`], which presents the picker and reports
+//! the picked files through a C callback, the same way `native/picker.m` does.
+
+use crate::{
+    file::system_time_from_secs, Bookmark, FileHandle, FileMetadata, NativeError, PickError,
+    PickEvent, PickOptions, PickedFile, Presenter,
+};
+use std::{
+    ffi::{c_char, c_void, CStr},
+    fmt::Debug,
+    io,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+
+mod fake;
+mod session;
+#[cfg(target_os = "ios")]
+mod uikit;
+mod unsupported;
+
+pub use fake::*;
+pub use session::{PickerSession, RequestId, SessionPolicy};
+#[cfg(target_os = "ios")]
+pub use uikit::UiKitBackend;
+pub use unsupported::UnsupportedBackend;
+
+/// The kind of a message sent to [`RawCallback`].
+///
+/// Keep in sync with `PickMessageKind` in `native/picker.m`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PickMessageKind {
+    /// A file is picked and read. The data points to a [`RawPickedFile`].
+    File = 0,
+    /// A file is picked but couldn't be read. The data points to a
+    /// [`RawPickError`].
+    FileError = 1,
+    /// The picker finishes. It is always the last message.
+    Finished = 2,
+    /// The picker is cancelled. It is followed by [`PickMessageKind::Finished`].
+    Cancelled = 3,
+    /// The picker couldn't be presented. The data points to a [`RawPickError`].
+    /// It is followed by [`PickMessageKind::Finished`].
+    Failed = 4,
+    /// A file is exported. The data points to the NUL-terminated URL of the
+    /// destination.
+    Exported = 5,
+    /// A range of a file is read. The data points to the bytes, which are
+    /// fewer than requested only at the end of the file.
+    Chunk = 6,
+    /// Something happens to the picker. The data points to a [`RawPickEvent`].
+    /// It is only sent if [`PickOptions::events`] is set.
+    Event = 7,
+}
+
+/// A file sent with [`PickMessageKind::File`].
+///
+/// The strings are nullable, NUL-terminated UTF-8, and only need to live
+/// during the callback, and so does the content unless it has an owner.
+#[repr(C)]
+#[derive(Debug)]
+pub struct RawPickedFile {
+    /// The content.
+    pub data: *const c_void,
+    /// The length of the content.
+    pub len: usize,
+    /// The owner of the content, or null if the content is only borrowed
+    /// during the callback.
+    ///
+    /// The receiver takes the ownership, and calls `release` with it exactly
+    /// once when the content isn't used anymore, which could be on any
+    /// thread.
+    pub owner: *mut c_void,
+    /// The function releasing `owner`.
+    pub release: Option<unsafe extern "C" fn(*mut c_void)>,
+    /// The file name.
+    pub name: *const c_char,
+    /// The absolute string of the URL.
+    pub url: *const c_char,
+    /// The local path of the URL.
+    pub path: *const c_char,
+    /// The bookmark data, or null if not requested.
+    pub bookmark: *const c_void,
+    /// The length of the bookmark data.
+    pub bookmark_len: usize,
+    /// The identifier of the UTType.
+    pub content_type: *const c_char,
+    /// The file size, or negative if unknown.
+    pub size: i64,
+    /// The creation date in seconds since the Unix epoch, or NaN if unknown.
+    pub created: f64,
+    /// The modification date in seconds since the Unix epoch, or NaN if
+    /// unknown.
+    pub modified: f64,
+    /// The name of the providing app or location.
+    pub provider: *const c_char,
+}
+
+impl RawPickedFile {
+    /// Convert to a [`PickedFile`], taking the ownership of the content if it
+    /// has an owner, or else copying it.
+    ///
+    /// # Safety
+    ///
+    /// The content must be valid for `len` bytes, and the strings must be null
+    /// or valid C strings. If there's an owner, it must be called only once.
+    pub unsafe fn to_file(&self) -> PickedFile {
+        let content = match self.release {
+            Some(release) if !self.owner.is_null() => {
+                FileHandle::from_native(self.data.cast(), self.len, self.owner, release)
+            }
+            _ if self.data.is_null() => FileHandle::from(Vec::new()),
+            _ => FileHandle::from(
+                std::slice::from_raw_parts(self.data as *const u8, self.len).to_vec(),
+            ),
+        };
+        let metadata = FileMetadata {
+            name: string(self.name).unwrap_or_default(),
+            url: string(self.url).unwrap_or_default(),
+            path: string(self.path).map(PathBuf::from),
+            bookmark: (!self.bookmark.is_null()).then(|| {
+                Bookmark::from_bytes(std::slice::from_raw_parts(
+                    self.bookmark as *const u8,
+                    self.bookmark_len,
+                ))
+            }),
+            content_type: string(self.content_type),
+            size: u64::try_from(self.size).ok(),
+            created: system_time_from_secs(self.created),
+            modified: system_time_from_secs(self.modified),
+            provider: string(self.provider),
+            sniffed: None,
+            request: None,
+        };
+        PickedFile::new(metadata, content)
+    }
+}
+
+unsafe fn string(s: *const c_char) -> Option<String> {
+    if s.is_null() {
+        None
+    } else {
+        Some(CStr::from_ptr(s).to_string_lossy().into_owned())
+    }
+}
+
+/// The kind of a [`RawPickError`].
+///
+/// Keep in sync with `PickErrorKind` in `native/picker.m`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RawPickErrorKind {
+    /// See [`PickError::AccessDenied`].
+    AccessDenied = 0,
+    /// See [`PickError::CoordinationFailed`].
+    CoordinationFailed = 1,
+    /// See [`PickError::ReadFailed`].
+    ReadFailed = 2,
+    /// See [`PickError::UnsupportedType`]. The description is the type.
+    UnsupportedType = 3,
+    /// See [`PickError::UnsupportedPlatform`].
+    UnsupportedPlatform = 4,
+    /// See [`PickError::BookmarkFailed`].
+    BookmarkFailed = 5,
+    /// See [`PickError::FileTooLarge`]. The description is the file name.
+    FileTooLarge = 6,
+    /// See [`PickError::BudgetExceeded`]. The description is the file name,
+    /// and the limit is the remaining budget.
+    BudgetExceeded = 7,
+    /// See [`PickError::NoPresenter`].
+    NoPresenter = 8,
+    /// See [`PickError::Busy`]. The size is the ID of the presented request.
+    Busy = 9,
+    /// See [`PickError::WriteFailed`].
+    WriteFailed = 10,
+    /// A [`PickError::Io`] of [`io::ErrorKind::InvalidInput`], like a path
+    /// which couldn't be passed to the native side. The description is the
+    /// message.
+    InvalidInput = 11,
+}
+
+/// An error sent with [`PickMessageKind::FileError`] or
+/// [`PickMessageKind::Failed`].
+///
+/// The strings are nullable, NUL-terminated UTF-8, and only need to live
+/// during the callback.
+#[repr(C)]
+#[derive(Debug)]
+pub struct RawPickError {
+    /// The kind of the error.
+    pub kind: RawPickErrorKind,
+    /// The domain of the underlying `NSError`.
+    pub domain: *const c_char,
+    /// The code of the underlying `NSError`.
+    pub code: isize,
+    /// The localized description of the underlying `NSError`.
+    pub description: *const c_char,
+    /// The file size, for the size errors.
+    pub size: u64,
+    /// The exceeded limit, for the size errors.
+    pub limit: u64,
+}
+
+impl RawPickError {
+    /// Convert to a [`PickError`].
+    ///
+    /// # Safety
+    ///
+    /// The strings must be null or valid C strings.
+    pub unsafe fn to_error(&self) -> PickError {
+        let native = || NativeError {
+            domain: string(self.domain).unwrap_or_default(),
+            code: self.code,
+            description: string(self.description).unwrap_or_default(),
+        };
+        match self.kind {
+            RawPickErrorKind::AccessDenied => PickError::AccessDenied,
+            RawPickErrorKind::CoordinationFailed => PickError::CoordinationFailed(native()),
+            RawPickErrorKind::ReadFailed => PickError::ReadFailed(native()),
+            RawPickErrorKind::WriteFailed => PickError::WriteFailed(native()),
+            RawPickErrorKind::UnsupportedType => {
+                PickError::UnsupportedType(string(self.description).unwrap_or_default())
+            }
+            RawPickErrorKind::UnsupportedPlatform => PickError::UnsupportedPlatform,
+            RawPickErrorKind::InvalidInput => PickError::Io {
+                kind: io::ErrorKind::InvalidInput,
+                message: string(self.description).unwrap_or_default(),
+            },
+            RawPickErrorKind::BookmarkFailed => PickError::BookmarkFailed(native()),
+            RawPickErrorKind::NoPresenter => PickError::NoPresenter,
+            RawPickErrorKind::Busy => PickError::Busy {
+                current: RequestId::from_raw(self.size),
+            },
+            RawPickErrorKind::FileTooLarge => PickError::FileTooLarge {
+                name: string(self.description).unwrap_or_default(),
+                size: self.size,
+                limit: self.limit,
+            },
+            RawPickErrorKind::BudgetExceeded => PickError::BudgetExceeded {
+                name: string(self.description).unwrap_or_default(),
+                size: self.size,
+                remaining: self.limit,
+            },
+        }
+    }
+}
+
+/// The kind of a [`RawPickEvent`].
+///
+/// Keep in sync with `PickEventKind` in `native/picker.m`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RawPickEventKind {
+    /// See [`PickEvent::Presented`].
+    Presented = 0,
+    /// See [`PickEvent::Dismissed`].
+    Dismissed = 1,
+    /// See [`PickEvent::FileStarted`].
+    FileStarted = 2,
+    /// See [`PickEvent::Progress`].
+    Progress = 3,
+    /// See [`PickEvent::FileFinished`].
+    FileFinished = 4,
+}
+
+/// An event sent with [`PickMessageKind::Event`].
+///
+/// The name is nullable, NUL-terminated UTF-8, and only needs to live during
+/// the callback.
+#[repr(C)]
+#[derive(Debug)]
+pub struct RawPickEvent {
+    /// The kind of the event.
+    pub kind: RawPickEventKind,
+    /// The index of the file in the selection, for the file events.
+    pub index: usize,
+    /// The file name, for [`RawPickEventKind::FileStarted`].
+    pub name: *const c_char,
+    /// The bytes read, for [`RawPickEventKind::Progress`].
+    pub completed: u64,
+    /// The file size, for [`RawPickEventKind::Progress`], which is only sent
+    /// for files of known size. It is -1 for the other events.
+    pub total: i64,
+}
+
+impl RawPickEvent {
+    /// Convert to a [`PickEvent`].
+    ///
+    /// # Safety
+    ///
+    /// The name must be null or a valid C string.
+    pub unsafe fn to_event(&self) -> PickEvent {
+        match self.kind {
+            RawPickEventKind::Presented => PickEvent::Presented,
+            RawPickEventKind::Dismissed => PickEvent::Dismissed,
+            RawPickEventKind::FileStarted => PickEvent::FileStarted {
+                index: self.index,
+                name: string(self.name).unwrap_or_default(),
+            },
+            RawPickEventKind::Progress => PickEvent::Progress {
+                index: self.index,
+                completed: self.completed,
+                total: u64::try_from(self.total).unwrap_or_default(),
+            },
+            RawPickEventKind::FileFinished => PickEvent::FileFinished { index: self.index },
+        }
+    }
+}
+
+/// The callback a backend reports picked files to.
+///
+/// It is called with [`PickMessageKind::File`] or
+/// [`PickMessageKind::FileError`] for every picked file, one at a time for
+/// each [`PresentationHandle::request_next`], or with
+/// [`PickMessageKind::Cancelled`] or [`PickMessageKind::Failed`] if the picker
+/// is cancelled or couldn't be presented, with [`PickMessageKind::Event`]s in
+/// between if they are requested. At last it is
+/// called exactly once with [`PickMessageKind::Finished`], after which the
+/// `callback_data` passed to [`PickerBackend::present`] is freed and must not
+/// be used again.
+pub type RawCallback = unsafe extern "C" fn(PickMessageKind, *const c_void, usize, *mut c_void);
+
+/// One picker presentation.
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub struct PickRequest<'a> {
+    /// The ID of the presentation.
+    pub id: RequestId,
+    /// Where the picker is presented.
+    pub presenter: &'a Presenter,
+    /// The options of the picker.
+    ///
+    /// Backends should ignore the files picked beyond
+    /// [`PickOptions::max_selection`]. If [`PickOptions::folder`] is set, the
+    /// picked folders are reported with [`PickMessageKind::File`] without any
+    /// content, and the access to them is kept until the presentation drops.
+    /// So are the files if [`PickOptions::streaming`] is set.
+    pub options: &'a PickOptions,
+}
+
+/// A coordinated change of a picked document, or of an item inside a picked
+/// folder.
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub enum WriteOperation<'a> {
+    /// Overwrite the file in place.
+    Write {
+        /// The local path of the file.
+        path: &'a Path,
+        /// The new content.
+        data: &'a [u8],
+    },
+    /// Replace the file atomically, so that it is never partially written.
+    Replace {
+        /// The local path of the file.
+        path: &'a Path,
+        /// The new content.
+        data: &'a [u8],
+    },
+    /// Create a file, failing if the path exists.
+    Create {
+        /// The local path of the new file.
+        path: &'a Path,
+        /// The content.
+        data: &'a [u8],
+    },
+    /// Move an item, failing if the destination exists.
+    Move {
+        /// The local path of the item.
+        from: &'a Path,
+        /// The new local path.
+        to: &'a Path,
+    },
+    /// Delete an item, with its contents if it is a directory.
+    Delete {
+        /// The local path of the item.
+        path: &'a Path,
+    },
+}
+
+/// The backend-specific state of a presented picker.
+///
+/// It could be used and dropped on any thread, and so a handle of UIKit
+/// objects must do the work on the main thread.
+pub trait PresentationHandle: Send + Sync {
+    /// Ask the picker to read and report the next picked file.
+    ///
+    /// The files are reported in order. No file is read before it is
+    /// requested, or at most [`PickOptions::concurrent_reads`] - 1 files
+    /// ahead, so that a slow consumer holds back the reading of the following
+    /// files.
+    fn request_next(&self);
+
+    /// Dismiss the picker if it is still presented, and stop reading the
+    /// picked files.
+    ///
+    /// If the picker hasn't finished, the callback is called with
+    /// [`PickMessageKind::Finished`] and nothing else afterwards. It is called
+    /// when the [`Presentation`] drops, and could be called more than once.
+    fn dismiss(&self);
+}
+
+/// One export picker presentation.
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub struct ExportRequest<'a> {
+    /// The ID of the presentation.
+    pub id: RequestId,
+    /// Where the picker is presented.
+    pub presenter: &'a Presenter,
+    /// The local files to export. They are copied to the destination.
+    pub paths: &'a [PathBuf],
+}
+
+/// A handle of a presented picker.
+///
+/// It is kept alive by the returned future or stream, and dismisses the
+/// picker when it drops.
+pub struct Presentation {
+    handle: Option<Box<dyn PresentationHandle>>,
+}
+
+impl Presentation {
+    /// Create a presentation with the backend-specific `handle`.
+    pub fn new(handle: impl PresentationHandle + 'static) -> Self {
+        Self {
+            handle: Some(Box::new(handle)),
+        }
+    }
+
+    /// Create a presentation without any state.
+    pub fn empty() -> Self {
+        Self { handle: None }
+    }
+
+    /// Ask the picker to read and report the next picked file.
+    pub fn request_next(&self) {
+        if let Some(handle) = &self.handle {
+            handle.request_next();
+        }
+    }
+
+    /// Dismiss the picker if it is still presented, and stop reading the
+    /// picked files.
+    pub fn dismiss(&self) {
+        if let Some(handle) = &self.handle {
+            handle.dismiss();
+        }
+    }
+}
+
+impl Drop for Presentation {
+    fn drop(&mut self) {
+        self.dismiss();
+    }
+}
+
+impl Debug for Presentation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Presentation").finish_non_exhaustive()
+    }
+}
+
+/// A backend which presents the document picker.
+pub trait PickerBackend: Send + Sync {
+    /// Present a picker for `request`.
+    ///
+    /// Implementations must call `callback` with `callback_data` following the
+    /// protocol described in [`RawCallback`].
+    ///
+    /// # Safety
+    ///
+    /// `callback_data` must be valid to be passed to `callback`.
+    unsafe fn present(
+        &self,
+        request: &PickRequest<'_>,
+        callback: RawCallback,
+        callback_data: *mut c_void,
+    ) -> Presentation;
+
+    /// Read a file with coordination, like a picked file is read.
+    ///
+    /// It is used to read the files inside a picked folder, while the access to
+    /// the folder is kept by its presentation. It reports
+    /// [`PickMessageKind::File`] or [`PickMessageKind::FileError`], and then
+    /// [`PickMessageKind::Finished`], without waiting for any request.
+    ///
+    /// # Safety
+    ///
+    /// `callback_data` must be valid to be passed to `callback`.
+    unsafe fn read_file(&self, path: &Path, callback: RawCallback, callback_data: *mut c_void);
+
+    /// Read at most `len` bytes of a file from `offset`, with coordination.
+    ///
+    /// It is used to stream the files picked with [`PickOptions::streaming`],
+    /// while the access to them is kept by their presentation. It reports
+    /// [`PickMessageKind::Chunk`] or [`PickMessageKind::FileError`], and then
+    /// [`PickMessageKind::Finished`].
+    ///
+    /// # Safety
+    ///
+    /// `callback_data` must be valid to be passed to `callback`.
+    unsafe fn read_range(
+        &self,
+        path: &Path,
+        offset: u64,
+        len: usize,
+        callback: RawCallback,
+        callback_data: *mut c_void,
+    );
+
+    /// Read the metadata of a file with coordination, without its content.
+    ///
+    /// It is used by [`LazyFile::stat`](crate::LazyFile::stat), while the
+    /// access to the file is kept by its presentation. It reports
+    /// [`PickMessageKind::File`] without content or
+    /// [`PickMessageKind::FileError`], and then [`PickMessageKind::Finished`].
+    ///
+    /// # Safety
+    ///
+    /// `callback_data` must be valid to be passed to `callback`.
+    unsafe fn stat(&self, path: &Path, callback: RawCallback, callback_data: *mut c_void);
+
+    /// Change a document with coordination, while the access to it is kept by
+    /// its presentation.
+    ///
+    /// It reports [`PickMessageKind::File`] with the metadata of the
+    /// resulting file without content, except for
+    /// [`WriteOperation::Delete`], or [`PickMessageKind::FileError`], and then
+    /// [`PickMessageKind::Finished`]. The data of the operation only needs to
+    /// live during the call.
+    ///
+    /// # Safety
+    ///
+    /// `callback_data` must be valid to be passed to `callback`.
+    unsafe fn write(
+        &self,
+        operation: &WriteOperation<'_>,
+        callback: RawCallback,
+        callback_data: *mut c_void,
+    );
+
+    /// Resolve the bookmark data of a document, and read it with
+    /// coordination.
+    ///
+    /// It reports like [`PickerBackend::read_file`]. The reported file carries
+    /// a refreshed bookmark if `bookmark` is stale, or else `bookmark` itself.
+    ///
+    /// # Safety
+    ///
+    /// `callback_data` must be valid to be passed to `callback`.
+    unsafe fn resolve_bookmark(
+        &self,
+        bookmark: &[u8],
+        callback: RawCallback,
+        callback_data: *mut c_void,
+    );
+
+    /// Present an export picker for `request`.
+    ///
+    /// It reports [`PickMessageKind::Exported`] for every exported file instead
+    /// of [`PickMessageKind::File`], and never waits for
+    /// [`PresentationHandle::request_next`].
+    ///
+    /// # Safety
+    ///
+    /// `callback_data` must be valid to be passed to `callback`.
+    unsafe fn present_export(
+        &self,
+        request: &ExportRequest<'_>,
+        callback: RawCallback,
+        callback_data: *mut c_void,
+    ) -> Presentation;
+}
+
+static DEFAULT_BACKEND: RwLock<Option<Arc<dyn PickerBackend>>> = RwLock::new(None);
+
+/// The backend used by [`pick_file`](crate::pick_file) and
+/// [`pick_files`](crate::pick_files).
+///
+/// It is a [`PickerSession`] queueing the picks of [`UiKitBackend`] on iOS,
+/// and [`UnsupportedBackend`] on other platforms, unless another one is set by
+/// [`set_default_backend`].
+pub fn default_backend() -> Arc<dyn PickerBackend> {
+    if let Some(backend) = DEFAULT_BACKEND.read().unwrap().as_ref() {
+        return backend.clone();
+    }
+    #[cfg(target_os = "ios")]
+    {
+        static SESSION: std::sync::OnceLock<Arc<PickerSession>> = std::sync::OnceLock::new();
+        SESSION
+            .get_or_init(|| Arc::new(PickerSession::new(Arc::new(UiKitBackend))))
+            .clone()
+    }
+    #[cfg(not(target_os = "ios"))]
+    {
+        Arc::new(UnsupportedBackend)
+    }
+}
+
+/// Set the backend used by [`pick_file`](crate::pick_file) and
+/// [`pick_files`](crate::pick_files).
+pub fn set_default_backend(backend: Arc<dyn PickerBackend>) {
+    *DEFAULT_BACKEND.write().unwrap() = Some(backend);
+}
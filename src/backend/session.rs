@@ -0,0 +1,421 @@
+use super::{
+    ExportRequest, PickMessageKind, PickRequest, PickerBackend, Presentation, PresentationHandle,
+    RawCallback, RawPickError, RawPickErrorKind, WriteOperation,
+};
+use crate::{PickOptions, Presenter};
+use std::{
+    collections::VecDeque,
+    ffi::c_void,
+    fmt::Display,
+    mem::size_of,
+    path::{Path, PathBuf},
+    ptr::null,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, Weak,
+    },
+};
+
+/// The ID of a picker presentation, unique in the process.
+///
+/// It is set in [`PickRequest::id`] and [`FileMetadata::request`], so that
+/// the logs of a backend and the results could be correlated.
+///
+/// [`FileMetadata::request`]: crate::FileMetadata::request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RequestId(u64);
+
+impl RequestId {
+    pub(crate) fn next() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(1);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+
+    pub(crate) fn from_raw(id: u64) -> Self {
+        Self(id)
+    }
+
+    /// The ID as a number.
+    pub fn get(self) -> u64 {
+        self.0
+    }
+}
+
+impl Display for RequestId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+/// What a [`PickerSession`] does with a request while another picker is
+/// presented.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SessionPolicy {
+    /// Present it after the pickers before it finish.
+    #[default]
+    Queue,
+    /// Fail it with [`PickError::Busy`](crate::PickError::Busy).
+    RejectBusy,
+    /// Dismiss the presented picker, whose pick is cancelled, and present it
+    /// next.
+    CancelCurrent,
+}
+
+/// A backend which presents one picker at a time.
+///
+/// UIKit refuses to present a picker over another one, and so concurrent
+/// picks have to go through one session, which handles them according to its
+/// [`SessionPolicy`]. A picker is done when its callback gets
+/// [`PickMessageKind::Finished`], after all of the picked files are read.
+///
+/// [`default_backend`](super::default_backend) is a session queueing the
+/// picks on iOS.
+///
+/// ```no_run
+/// # use file_picker_ios::{backend::{PickerSession, SessionPolicy, UnsupportedBackend}, FilePicker};
+/// # use std::sync::Arc;
+/// let session = Arc::new(
+///     PickerSession::new(Arc::new(UnsupportedBackend)).policy(SessionPolicy::RejectBusy),
+/// );
+/// let picker = FilePicker::new().backend(session);
+/// ```
+pub struct PickerSession {
+    backend: Arc<dyn PickerBackend>,
+    policy: SessionPolicy,
+    state: Arc<Mutex<SessionState>>,
+}
+
+impl PickerSession {
+    /// Create a session presenting with `backend`, queueing the requests.
+    pub fn new(backend: Arc<dyn PickerBackend>) -> Self {
+        Self {
+            backend,
+            policy: SessionPolicy::default(),
+            state: Arc::default(),
+        }
+    }
+
+    /// Set the policy of the requests made while a picker is presented.
+    pub fn policy(mut self, policy: SessionPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// The request of the presented picker.
+    pub fn current(&self) -> Option<RequestId> {
+        self.state.lock().unwrap().current.as_ref().map(|c| c.id)
+    }
+
+    /// The requests waiting to be presented, in order.
+    pub fn queued(&self) -> Vec<RequestId> {
+        let state = self.state.lock().unwrap();
+        state.queue.iter().map(|q| q.id).collect()
+    }
+
+    unsafe fn submit(
+        &self,
+        id: RequestId,
+        request: OwnedRequest,
+        callback: RawCallback,
+        callback_data: *mut c_void,
+    ) -> Presentation {
+        let slot = Arc::new(Mutex::new(Slot::default()));
+        let queued = Queued {
+            id,
+            request,
+            callback,
+            callback_data: CallbackData(callback_data),
+            slot: slot.clone(),
+        };
+        let mut state = self.state.lock().unwrap();
+        match &state.current {
+            None => {
+                state.current = Some(Current {
+                    id,
+                    slot: Arc::downgrade(&slot),
+                });
+                drop(state);
+                start(&self.backend, &self.state, queued);
+            }
+            Some(current) => match self.policy {
+                SessionPolicy::Queue => state.queue.push_back(queued),
+                SessionPolicy::RejectBusy => {
+                    let current = current.id;
+                    drop(state);
+                    reject(callback, callback_data, current);
+                    return Presentation::empty();
+                }
+                SessionPolicy::CancelCurrent => {
+                    let current = current.slot.clone();
+                    state.queue.push_front(queued);
+                    drop(state);
+                    if let Some(current) = current.upgrade() {
+                        current.lock().unwrap().dismiss();
+                    }
+                }
+            },
+        }
+        Presentation::new(SessionHandle {
+            id,
+            slot,
+            state: Arc::downgrade(&self.state),
+        })
+    }
+}
+
+impl std::fmt::Debug for PickerSession {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PickerSession")
+            .field("policy", &self.policy)
+            .field("current", &self.current())
+            .field("queued", &self.queued())
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Default)]
+struct SessionState {
+    current: Option<Current>,
+    queue: VecDeque<Queued>,
+}
+
+struct Current {
+    id: RequestId,
+    slot: Weak<Mutex<Slot>>,
+}
+
+enum OwnedRequest {
+    Pick(Presenter, PickOptions),
+    Export(Presenter, Vec<PathBuf>),
+}
+
+struct CallbackData(*mut c_void);
+
+// SAFETY: the callback data is owned by the callback.
+unsafe impl Send for CallbackData {}
+
+struct Queued {
+    id: RequestId,
+    request: OwnedRequest,
+    callback: RawCallback,
+    callback_data: CallbackData,
+    slot: Arc<Mutex<Slot>>,
+}
+
+/// The state of one request, shared by its handle and the session.
+#[derive(Default)]
+struct Slot {
+    presentation: Option<Presentation>,
+    // The requests made before the picker is presented.
+    requested: usize,
+    dismissed: bool,
+}
+
+impl Slot {
+    fn dismiss(&mut self) {
+        self.dismissed = true;
+        if let Some(presentation) = &self.presentation {
+            presentation.dismiss();
+        }
+    }
+}
+
+/// Forwards the messages of a presented picker, and presents the next
+/// request when it finishes.
+struct Relay {
+    callback: RawCallback,
+    callback_data: *mut c_void,
+    backend: Arc<dyn PickerBackend>,
+    state: Weak<Mutex<SessionState>>,
+}
+
+unsafe extern "C" fn relay_callback(
+    kind: PickMessageKind,
+    data: *const c_void,
+    len: usize,
+    relay: *mut c_void,
+) {
+    let relay_ptr = relay as *mut Relay;
+    ((*relay_ptr).callback)(kind, data, len, (*relay_ptr).callback_data);
+    if kind == PickMessageKind::Finished {
+        let relay = Box::from_raw(relay_ptr);
+        if let Some(state) = relay.state.upgrade() {
+            finish(&relay.backend, &state);
+        }
+    }
+}
+
+/// Present the next request, if any, after the current one finishes.
+fn finish(backend: &Arc<dyn PickerBackend>, state: &Arc<Mutex<SessionState>>) {
+    let mut guard = state.lock().unwrap();
+    guard.current = None;
+    if let Some(next) = guard.queue.pop_front() {
+        guard.current = Some(Current {
+            id: next.id,
+            slot: Arc::downgrade(&next.slot),
+        });
+        drop(guard);
+        unsafe { start(backend, state, next) };
+    }
+}
+
+/// Present a request with the backend, without holding any lock, as the
+/// backend may call back at once.
+unsafe fn start(
+    backend: &Arc<dyn PickerBackend>,
+    state: &Arc<Mutex<SessionState>>,
+    queued: Queued,
+) {
+    let relay = Box::into_raw(Box::new(Relay {
+        callback: queued.callback,
+        callback_data: queued.callback_data.0,
+        backend: backend.clone(),
+        state: Arc::downgrade(state),
+    }));
+    let presentation = match &queued.request {
+        OwnedRequest::Pick(presenter, options) => backend.present(
+            &PickRequest {
+                id: queued.id,
+                presenter,
+                options,
+            },
+            relay_callback,
+            relay.cast(),
+        ),
+        OwnedRequest::Export(presenter, paths) => backend.present_export(
+            &ExportRequest {
+                id: queued.id,
+                presenter,
+                paths,
+            },
+            relay_callback,
+            relay.cast(),
+        ),
+    };
+    let mut slot = queued.slot.lock().unwrap();
+    if slot.dismissed {
+        presentation.dismiss();
+    } else {
+        for _ in 0..slot.requested {
+            presentation.request_next();
+        }
+    }
+    slot.presentation = Some(presentation);
+}
+
+unsafe fn reject(callback: RawCallback, callback_data: *mut c_void, current: RequestId) {
+    let error = RawPickError {
+        kind: RawPickErrorKind::Busy,
+        domain: null(),
+        code: 0,
+        description: null(),
+        size: current.0,
+        limit: 0,
+    };
+    callback(
+        PickMessageKind::Failed,
+        (&error as *const RawPickError).cast(),
+        size_of::<RawPickError>(),
+        callback_data,
+    );
+    callback(PickMessageKind::Finished, null(), 0, callback_data);
+}
+
+struct SessionHandle {
+    id: RequestId,
+    slot: Arc<Mutex<Slot>>,
+    state: Weak<Mutex<SessionState>>,
+}
+
+impl PresentationHandle for SessionHandle {
+    fn request_next(&self) {
+        let mut slot = self.slot.lock().unwrap();
+        match &slot.presentation {
+            Some(presentation) => presentation.request_next(),
+            None => slot.requested += 1,
+        }
+    }
+
+    fn dismiss(&self) {
+        {
+            let mut slot = self.slot.lock().unwrap();
+            slot.dismiss();
+            if slot.presentation.is_some() {
+                return;
+            }
+        }
+        // A queued request finishes without being presented. If it isn't in
+        // the queue, it is being presented, and sees that it is dismissed.
+        let Some(state) = self.state.upgrade() else {
+            return;
+        };
+        let mut state = state.lock().unwrap();
+        let Some(index) = state.queue.iter().position(|q| q.id == self.id) else {
+            return;
+        };
+        let queued = state.queue.remove(index).unwrap();
+        drop(state);
+        unsafe { (queued.callback)(PickMessageKind::Finished, null(), 0, queued.callback_data.0) };
+    }
+}
+
+impl PickerBackend for PickerSession {
+    unsafe fn present(
+        &self,
+        request: &PickRequest<'_>,
+        callback: RawCallback,
+        callback_data: *mut c_void,
+    ) -> Presentation {
+        let owned = OwnedRequest::Pick(request.presenter.clone(), request.options.clone());
+        self.submit(request.id, owned, callback, callback_data)
+    }
+
+    unsafe fn read_file(&self, path: &Path, callback: RawCallback, callback_data: *mut c_void) {
+        self.backend.read_file(path, callback, callback_data);
+    }
+
+    unsafe fn read_range(
+        &self,
+        path: &Path,
+        offset: u64,
+        len: usize,
+        callback: RawCallback,
+        callback_data: *mut c_void,
+    ) {
+        self.backend
+            .read_range(path, offset, len, callback, callback_data);
+    }
+
+    unsafe fn stat(&self, path: &Path, callback: RawCallback, callback_data: *mut c_void) {
+        self.backend.stat(path, callback, callback_data);
+    }
+
+    unsafe fn write(
+        &self,
+        operation: &WriteOperation<'_>,
+        callback: RawCallback,
+        callback_data: *mut c_void,
+    ) {
+        self.backend.write(operation, callback, callback_data);
+    }
+
+    unsafe fn resolve_bookmark(
+        &self,
+        bookmark: &[u8],
+        callback: RawCallback,
+        callback_data: *mut c_void,
+    ) {
+        self.backend
+            .resolve_bookmark(bookmark, callback, callback_data);
+    }
+
+    unsafe fn present_export(
+        &self,
+        request: &ExportRequest<'_>,
+        callback: RawCallback,
+        callback_data: *mut c_void,
+    ) -> Presentation {
+        let owned = OwnedRequest::Export(request.presenter.clone(), request.paths.to_vec());
+        self.submit(request.id, owned, callback, callback_data)
+    }
+}
@@ -0,0 +1,410 @@
+use super::{
+    ExportRequest, PickMessageKind, PickRequest, PickerBackend, Presentation, PresentationHandle,
+    RawCallback, RawPickError, RawPickErrorKind, WriteOperation,
+};
+use crate::{
+    main_thread::{run_on_main, MainObject, MainThread},
+    PickMode, PickOptions, Presenter,
+};
+use objc::runtime::Object;
+use std::{
+    ffi::{c_char, c_void, CString},
+    mem::size_of,
+    os::unix::ffi::OsStrExt,
+    path::Path,
+    ptr::null,
+};
+
+// Keep in sync with `PickerOptions` in `native/picker.m`.
+#[repr(C)]
+struct RawPickerOptions {
+    extensions: *const *const c_char,
+    types_len: usize,
+    identifiers: *const *const c_char,
+    identifiers_len: usize,
+    allow_multiple: bool,
+    max_selection: usize,
+    initial_directory: *const c_char,
+    show_extensions: bool,
+    as_copy: bool,
+    presentation_style: isize,
+    folder: bool,
+    bookmarks: bool,
+    streaming: bool,
+    max_file_size: u64,
+    byte_budget: u64,
+    concurrent_reads: usize,
+    events: bool,
+}
+
+// Keep in sync with `WriteOperationKind` in `native/picker.m`.
+#[repr(C)]
+enum RawWriteKind {
+    Write = 0,
+    Replace = 1,
+    Create = 2,
+    Move = 3,
+    Delete = 4,
+}
+
+// Keep in sync with `RawWriteOperation` in `native/picker.m`.
+#[repr(C)]
+struct RawWriteOperation {
+    kind: RawWriteKind,
+    path: *const c_char,
+    destination: *const c_char,
+    data: *const c_void,
+    len: usize,
+}
+
+#[link(name = "UIKit", kind = "framework")]
+#[link(name = "UniformTypeIdentifiers", kind = "framework")]
+#[link(name = "picker", kind = "static")]
+extern "C" {
+    fn picker_create_delegate(closure: RawCallback, closure_data: *mut c_void) -> *mut Object;
+
+    fn show_browser(
+        presenter: *mut Object,
+        options: *const RawPickerOptions,
+        delegate: *mut Object,
+    );
+
+    fn show_exporter(
+        presenter: *mut Object,
+        paths: *const *const c_char,
+        paths_len: usize,
+        delegate: *mut Object,
+    );
+
+    fn picker_request_next(delegate: *mut Object);
+
+    fn picker_dismiss(delegate: *mut Object);
+
+    fn picker_read_file(path: *const c_char, closure: RawCallback, closure_data: *mut c_void);
+
+    fn picker_read_range(
+        path: *const c_char,
+        offset: u64,
+        len: usize,
+        closure: RawCallback,
+        closure_data: *mut c_void,
+    );
+
+    fn picker_stat(path: *const c_char, closure: RawCallback, closure_data: *mut c_void);
+
+    fn picker_write(
+        operation: *const RawWriteOperation,
+        closure: RawCallback,
+        closure_data: *mut c_void,
+    );
+
+    fn picker_resolve_bookmark(
+        bookmark: *const c_void,
+        bookmark_len: usize,
+        closure: RawCallback,
+        closure_data: *mut c_void,
+    );
+}
+
+/// The backend presenting a `UIDocumentPickerViewController`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct UiKitBackend;
+
+/// The strings of [`PickOptions`] passed to the native side.
+struct BrowserStrings {
+    extensions: Vec<CString>,
+    identifiers: Vec<CString>,
+    initial_directory: Option<CString>,
+}
+
+impl BrowserStrings {
+    /// Convert the strings of `options`, which fails on an interior NUL.
+    fn new(options: &PickOptions) -> Result<Self, Failure> {
+        let extensions = options
+            .extensions
+            .iter()
+            .map(|e| CString::new(e.as_str()).map_err(|_| Failure::unsupported_type(e)))
+            .collect::<Result<_, _>>()?;
+        let identifiers = options
+            .content_types
+            .iter()
+            .map(|t| {
+                CString::new(t.identifier()).map_err(|_| Failure::unsupported_type(t.identifier()))
+            })
+            .collect::<Result<_, _>>()?;
+        let initial_directory = options
+            .initial_directory
+            .as_deref()
+            .map(path_cstring)
+            .transpose()?;
+        Ok(Self {
+            extensions,
+            identifiers,
+            initial_directory,
+        })
+    }
+}
+
+/// An error found before reaching the native side.
+struct Failure {
+    kind: RawPickErrorKind,
+    description: CString,
+}
+
+impl Failure {
+    fn unsupported_type(name: &str) -> Self {
+        Self::new(RawPickErrorKind::UnsupportedType, name.replace('\0', "\\0"))
+    }
+
+    fn invalid_path(path: &Path) -> Self {
+        Self::new(
+            RawPickErrorKind::InvalidInput,
+            format!("{path:?} contains a NUL byte"),
+        )
+    }
+
+    fn new(kind: RawPickErrorKind, description: String) -> Self {
+        Self {
+            kind,
+            description: CString::new(description).unwrap_or_default(),
+        }
+    }
+
+    /// Report the error with a message of `kind`, and finish.
+    unsafe fn report(
+        &self,
+        kind: PickMessageKind,
+        callback: RawCallback,
+        callback_data: *mut c_void,
+    ) -> Presentation {
+        let error = RawPickError {
+            kind: self.kind,
+            domain: null(),
+            code: 0,
+            description: self.description.as_ptr(),
+            size: 0,
+            limit: 0,
+        };
+        callback(
+            kind,
+            (&error as *const RawPickError).cast(),
+            size_of::<RawPickError>(),
+            callback_data,
+        );
+        callback(PickMessageKind::Finished, null(), 0, callback_data);
+        Presentation::empty()
+    }
+}
+
+fn path_cstring(path: &Path) -> Result<CString, Failure> {
+    CString::new(path.as_os_str().as_bytes()).map_err(|_| Failure::invalid_path(path))
+}
+
+/// Presents the browser of `delegate` on the main thread.
+fn present_browser(
+    main: MainThread,
+    presenter: &Presenter,
+    options: &PickOptions,
+    strings: &BrowserStrings,
+    delegate: &MainObject,
+) {
+    with_string_ptrs(&strings.extensions, |extension_ptrs| {
+        with_string_ptrs(&strings.identifiers, |identifier_ptrs| {
+            let raw_options = RawPickerOptions {
+                extensions: extension_ptrs.as_ptr(),
+                types_len: extension_ptrs.len(),
+                identifiers: identifier_ptrs.as_ptr(),
+                identifiers_len: identifier_ptrs.len(),
+                allow_multiple: options.allow_multiple,
+                max_selection: options.max_selection.unwrap_or(usize::MAX),
+                initial_directory: strings
+                    .initial_directory
+                    .as_ref()
+                    .map_or(null(), |s| s.as_ptr()),
+                show_extensions: options.show_extensions,
+                as_copy: options.mode == PickMode::Copy,
+                presentation_style: options.presentation_style as isize,
+                folder: options.folder,
+                bookmarks: options.bookmarks,
+                streaming: options.streaming,
+                max_file_size: options.max_file_size.unwrap_or(u64::MAX),
+                byte_budget: options.byte_budget.unwrap_or(u64::MAX),
+                concurrent_reads: options.concurrent_reads,
+                events: options.events,
+            };
+            unsafe { show_browser(presenter.get(main), &raw_options, delegate.get(main)) };
+        })
+    })
+}
+
+fn present_exporter(
+    main: MainThread,
+    presenter: &Presenter,
+    paths: &[CString],
+    delegate: &MainObject,
+) {
+    let path_ptrs = paths.iter().map(|s| s.as_ptr()).collect::<Vec<_>>();
+    unsafe {
+        show_exporter(
+            presenter.get(main),
+            path_ptrs.as_ptr(),
+            path_ptrs.len(),
+            delegate.get(main),
+        )
+    };
+}
+
+struct UiKitHandle(MainObject);
+
+impl PresentationHandle for UiKitHandle {
+    fn request_next(&self) {
+        let delegate = self.0.clone();
+        run_on_main(move |main| unsafe { picker_request_next(delegate.get(main)) });
+    }
+
+    fn dismiss(&self) {
+        let delegate = self.0.clone();
+        run_on_main(move |main| unsafe { picker_dismiss(delegate.get(main)) });
+    }
+}
+
+fn with_string_ptrs<T>(strings: &[CString], f: impl FnOnce(&[*const c_char]) -> T) -> T {
+    let ptrs = strings.iter().map(|s| s.as_ptr()).collect::<Vec<_>>();
+    f(&ptrs)
+}
+
+impl PickerBackend for UiKitBackend {
+    unsafe fn present(
+        &self,
+        request: &PickRequest<'_>,
+        callback: RawCallback,
+        callback_data: *mut c_void,
+    ) -> Presentation {
+        // The strings are checked here, as nothing could fail on the main
+        // queue.
+        let strings = match BrowserStrings::new(request.options) {
+            Ok(strings) => strings,
+            Err(failure) => {
+                return failure.report(PickMessageKind::Failed, callback, callback_data)
+            }
+        };
+        let delegate = MainObject::from_retained(picker_create_delegate(callback, callback_data));
+        let presenter = request.presenter.clone();
+        let options = request.options.clone();
+        let browser_delegate = delegate.clone();
+        run_on_main(move |main| {
+            present_browser(main, &presenter, &options, &strings, &browser_delegate)
+        });
+        Presentation::new(UiKitHandle(delegate))
+    }
+
+    unsafe fn read_file(&self, path: &Path, callback: RawCallback, callback_data: *mut c_void) {
+        let path = match path_cstring(path) {
+            Ok(path) => path,
+            Err(failure) => {
+                failure.report(PickMessageKind::FileError, callback, callback_data);
+                return;
+            }
+        };
+        picker_read_file(path.as_ptr(), callback, callback_data);
+    }
+
+    unsafe fn read_range(
+        &self,
+        path: &Path,
+        offset: u64,
+        len: usize,
+        callback: RawCallback,
+        callback_data: *mut c_void,
+    ) {
+        let path = match path_cstring(path) {
+            Ok(path) => path,
+            Err(failure) => {
+                failure.report(PickMessageKind::FileError, callback, callback_data);
+                return;
+            }
+        };
+        picker_read_range(path.as_ptr(), offset, len, callback, callback_data);
+    }
+
+    unsafe fn stat(&self, path: &Path, callback: RawCallback, callback_data: *mut c_void) {
+        let path = match path_cstring(path) {
+            Ok(path) => path,
+            Err(failure) => {
+                failure.report(PickMessageKind::FileError, callback, callback_data);
+                return;
+            }
+        };
+        picker_stat(path.as_ptr(), callback, callback_data);
+    }
+
+    unsafe fn write(
+        &self,
+        operation: &WriteOperation<'_>,
+        callback: RawCallback,
+        callback_data: *mut c_void,
+    ) {
+        let (kind, path, destination, data) = match *operation {
+            WriteOperation::Write { path, data } => (RawWriteKind::Write, path, None, data),
+            WriteOperation::Replace { path, data } => (RawWriteKind::Replace, path, None, data),
+            WriteOperation::Create { path, data } => (RawWriteKind::Create, path, None, data),
+            WriteOperation::Move { from, to } => (RawWriteKind::Move, from, Some(to), &[][..]),
+            WriteOperation::Delete { path } => (RawWriteKind::Delete, path, None, &[][..]),
+        };
+        let paths = path_cstring(path)
+            .and_then(|path| Ok((path, destination.map(path_cstring).transpose()?)));
+        let (path, destination) = match paths {
+            Ok(paths) => paths,
+            Err(failure) => {
+                failure.report(PickMessageKind::FileError, callback, callback_data);
+                return;
+            }
+        };
+        let raw = RawWriteOperation {
+            kind,
+            path: path.as_ptr(),
+            destination: destination.as_ref().map_or(null(), |s| s.as_ptr()),
+            data: data.as_ptr().cast(),
+            len: data.len(),
+        };
+        picker_write(&raw, callback, callback_data);
+    }
+
+    unsafe fn resolve_bookmark(
+        &self,
+        bookmark: &[u8],
+        callback: RawCallback,
+        callback_data: *mut c_void,
+    ) {
+        picker_resolve_bookmark(
+            bookmark.as_ptr().cast(),
+            bookmark.len(),
+            callback,
+            callback_data,
+        );
+    }
+
+    unsafe fn present_export(
+        &self,
+        request: &ExportRequest<'_>,
+        callback: RawCallback,
+        callback_data: *mut c_void,
+    ) -> Presentation {
+        let paths = match request
+            .paths
+            .iter()
+            .map(|p| path_cstring(p))
+            .collect::<Result<Vec<_>, _>>()
+        {
+            Ok(paths) => paths,
+            Err(failure) => {
+                return failure.report(PickMessageKind::Failed, callback, callback_data)
+            }
+        };
+        let delegate = MainObject::from_retained(picker_create_delegate(callback, callback_data));
+        let presenter = request.presenter.clone();
+        let exporter_delegate = delegate.clone();
+        run_on_main(move |main| present_exporter(main, &presenter, &paths, &exporter_delegate));
+        Presentation::new(UiKitHandle(delegate))
+    }
+}
@@ -0,0 +1,92 @@
+use super::{
+    ExportRequest, PickMessageKind, PickRequest, PickerBackend, Presentation, RawCallback,
+    RawPickError, RawPickErrorKind, WriteOperation,
+};
+use std::{ffi::c_void, mem::size_of, path::Path, ptr::null};
+
+/// The backend of platforms without a document picker.
+///
+/// It never presents anything, and every pick fails with
+/// [`PickError::UnsupportedPlatform`](crate::PickError::UnsupportedPlatform).
+#[derive(Debug, Default, Clone, Copy)]
+pub struct UnsupportedBackend;
+
+unsafe fn fail(
+    kind: PickMessageKind,
+    callback: RawCallback,
+    callback_data: *mut c_void,
+) -> Presentation {
+    let error = RawPickError {
+        kind: RawPickErrorKind::UnsupportedPlatform,
+        domain: null(),
+        code: 0,
+        description: null(),
+        size: 0,
+        limit: 0,
+    };
+    callback(
+        kind,
+        (&error as *const RawPickError).cast(),
+        size_of::<RawPickError>(),
+        callback_data,
+    );
+    callback(PickMessageKind::Finished, null(), 0, callback_data);
+    Presentation::empty()
+}
+
+impl PickerBackend for UnsupportedBackend {
+    unsafe fn present(
+        &self,
+        _request: &PickRequest<'_>,
+        callback: RawCallback,
+        callback_data: *mut c_void,
+    ) -> Presentation {
+        fail(PickMessageKind::Failed, callback, callback_data)
+    }
+
+    unsafe fn read_file(&self, _path: &Path, callback: RawCallback, callback_data: *mut c_void) {
+        fail(PickMessageKind::FileError, callback, callback_data);
+    }
+
+    unsafe fn read_range(
+        &self,
+        _path: &Path,
+        _offset: u64,
+        _len: usize,
+        callback: RawCallback,
+        callback_data: *mut c_void,
+    ) {
+        fail(PickMessageKind::FileError, callback, callback_data);
+    }
+
+    unsafe fn stat(&self, _path: &Path, callback: RawCallback, callback_data: *mut c_void) {
+        fail(PickMessageKind::FileError, callback, callback_data);
+    }
+
+    unsafe fn write(
+        &self,
+        _operation: &WriteOperation<'_>,
+        callback: RawCallback,
+        callback_data: *mut c_void,
+    ) {
+        fail(PickMessageKind::FileError, callback, callback_data);
+    }
+
+    unsafe fn resolve_bookmark(
+        &self,
+        _bookmark: &[u8],
+        callback: RawCallback,
+        callback_data: *mut c_void,
+    ) {
+        fail(PickMessageKind::FileError, callback, callback_data);
+    }
+
+    unsafe fn present_export(
+        &self,
+        _request: &ExportRequest<'_>,
+        callback: RawCallback,
+        callback_data: *mut c_void,
+    ) -> Presentation {
+        fail(PickMessageKind::Failed, callback, callback_data)
+    }
+}
@@ -0,0 +1,202 @@
+use crate::{
+    backend::{default_backend, PickerBackend},
+    context::CallbackContext,
+    format::{write_atomically, Decoder, Encoder},
+    picker::{pick_file_closure, PickFileSink},
+    PickError, PickedFile,
+};
+use futures_channel::oneshot;
+use std::{
+    collections::BTreeMap,
+    fs,
+    future::Future,
+    io,
+    path::{Path, PathBuf},
+};
+
+/// The bookmark data of a picked document, to reopen it later.
+///
+/// It is produced when [`PickOptions::bookmarks`](crate::PickOptions::bookmarks)
+/// is set, and could be persisted with [`BookmarkStore`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Bookmark(Vec<u8>);
+
+impl Bookmark {
+    /// Create from the bytes of a stored bookmark.
+    pub fn from_bytes(bytes: impl Into<Vec<u8>>) -> Self {
+        Self(bytes.into())
+    }
+
+    /// The bytes of the bookmark.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Convert into the bytes of the bookmark.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+/// Reopen the document of a bookmark and read it.
+///
+/// The document is read by [`default_backend`]. If the bookmark is stale, the
+/// metadata of the file carries a refreshed [`FileMetadata::bookmark`] to be
+/// stored instead; otherwise it carries the same bookmark.
+///
+/// [`FileMetadata::bookmark`]: crate::FileMetadata::bookmark
+pub fn open_bookmark(
+    bookmark: &Bookmark,
+) -> impl Future<Output = Result<PickedFile, PickError>> + Send + Sync {
+    open_bookmark_with(&*default_backend(), bookmark)
+}
+
+/// Reopen the document of a bookmark with the specified backend.
+pub fn open_bookmark_with(
+    backend: &dyn PickerBackend,
+    bookmark: &Bookmark,
+) -> impl Future<Output = Result<PickedFile, PickError>> + Send + Sync {
+    let (tx, rx) = oneshot::channel();
+    unsafe {
+        backend.resolve_bookmark(
+            bookmark.as_bytes(),
+            pick_file_closure,
+            CallbackContext::into_raw(PickFileSink(Some(tx))),
+        );
+    }
+    async move { rx.await.unwrap_or(Err(PickError::Cancelled)) }
+}
+
+const MAGIC: &[u8; 4] = b"FPBK";
+const VERSION: u32 = 1;
+
+/// Named bookmarks persisted in one file.
+///
+/// The file has the magic `FPBK` and a format version. Files of another
+/// version are rejected rather than misread.
+#[derive(Debug, Clone)]
+pub struct BookmarkStore {
+    path: PathBuf,
+    entries: BTreeMap<String, Bookmark>,
+}
+
+impl BookmarkStore {
+    /// Load the store at `path`. A missing file is an empty store.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, PickError> {
+        let path = path.into();
+        let entries = match fs::read(&path) {
+            Ok(data) => decode(&data)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self { path, entries })
+    }
+
+    /// The path of the store file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The bookmark of `key`.
+    pub fn get(&self, key: &str) -> Option<&Bookmark> {
+        self.entries.get(key)
+    }
+
+    /// Set the bookmark of `key`, returning the previous one.
+    pub fn insert(&mut self, key: impl Into<String>, bookmark: Bookmark) -> Option<Bookmark> {
+        self.entries.insert(key.into(), bookmark)
+    }
+
+    /// Remove the bookmark of `key`.
+    pub fn remove(&mut self, key: &str) -> Option<Bookmark> {
+        self.entries.remove(key)
+    }
+
+    /// The bookmarks sorted by key.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Bookmark)> {
+        self.entries.iter().map(|(k, v)| (k.as_str(), v))
+    }
+
+    /// The count of bookmarks.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether there's no bookmark.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Write the store to its file, replacing it atomically.
+    pub fn save(&self) -> Result<(), PickError> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        write_atomically(&self.path, &encode(&self.entries))?;
+        Ok(())
+    }
+
+    /// Reopen the document of `key` with [`default_backend`].
+    ///
+    /// See [`BookmarkStore::reopen_with`].
+    pub fn reopen<'a>(
+        &'a mut self,
+        key: &str,
+    ) -> impl Future<Output = Result<PickedFile, PickError>> + Send + Sync + 'a {
+        self.reopen_with(&*default_backend(), key)
+    }
+
+    /// Reopen the document of `key` with the specified backend.
+    ///
+    /// A stale bookmark is replaced by the refreshed one, and the store is
+    /// saved.
+    pub fn reopen_with<'a>(
+        &'a mut self,
+        backend: &dyn PickerBackend,
+        key: &str,
+    ) -> impl Future<Output = Result<PickedFile, PickError>> + Send + Sync + 'a {
+        let key = key.to_string();
+        let open = self
+            .entries
+            .get(&key)
+            .map(|bookmark| open_bookmark_with(backend, bookmark));
+        async move {
+            let Some(open) = open else {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("no bookmark of {key}"),
+                )
+                .into());
+            };
+            let file = open.await?;
+            if let Some(bookmark) = &file.metadata().bookmark {
+                if self.entries.get(&key) != Some(bookmark) {
+                    self.entries.insert(key, bookmark.clone());
+                    self.save()?;
+                }
+            }
+            Ok(file)
+        }
+    }
+}
+
+fn encode(entries: &BTreeMap<String, Bookmark>) -> Vec<u8> {
+    let mut encoder = Encoder::new(MAGIC, VERSION);
+    encoder.u32(entries.len() as u32);
+    for (key, bookmark) in entries {
+        encoder.bytes(key.as_bytes());
+        encoder.bytes(bookmark.as_bytes());
+    }
+    encoder.finish()
+}
+
+fn decode(data: &[u8]) -> io::Result<BTreeMap<String, Bookmark>> {
+    let mut decoder = Decoder::new(data, MAGIC, VERSION, "bookmark store")?;
+    let count = decoder.u32()?;
+    let mut entries = BTreeMap::new();
+    for _ in 0..count {
+        let key = decoder.string()?;
+        entries.insert(key, Bookmark::from_bytes(decoder.bytes()?));
+    }
+    Ok(entries)
+}
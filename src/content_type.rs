@@ -0,0 +1,208 @@
+use crate::PickError;
+use std::{borrow::Cow, fmt::Display, str::FromStr};
+
+struct TypeInfo {
+    identifier: &'static str,
+    conforms_to: &'static [&'static str],
+    extensions: &'static [&'static str],
+    mime_types: &'static [&'static str],
+}
+
+macro_rules! types {
+    ($($id:literal: [$($parent:literal),*] [$($ext:literal),*] [$($mime:literal),*],)*) => {
+        &[$(TypeInfo {
+            identifier: $id,
+            conforms_to: &[$($parent),*],
+            extensions: &[$($ext),*],
+            mime_types: &[$($mime),*],
+        },)*]
+    };
+}
+
+// A subset of the system-declared types, with the first extension and MIME
+// type of each being the preferred ones.
+static TYPES: &[TypeInfo] = types! {
+    "public.item": [] [] [],
+    "public.content": ["public.item"] [] [],
+    "public.data": ["public.item"] [] [],
+    "public.composite-content": ["public.content"] [] [],
+    "public.directory": ["public.item"] [] [],
+    "public.folder": ["public.directory"] [] [],
+    "public.text": ["public.data", "public.content"] [] [],
+    "public.plain-text": ["public.text"] ["txt", "text"] ["text/plain"],
+    "public.utf8-plain-text": ["public.plain-text"] [] [],
+    "net.daringfireball.markdown": ["public.plain-text"] ["md", "markdown"] ["text/markdown"],
+    "public.html": ["public.text"] ["html", "htm"] ["text/html"],
+    "public.xml": ["public.text"] ["xml"] ["application/xml", "text/xml"],
+    "public.json": ["public.text"] ["json"] ["application/json"],
+    "public.comma-separated-values-text": ["public.text"] ["csv"] ["text/csv"],
+    "public.rtf": ["public.text"] ["rtf"] ["text/rtf", "application/rtf"],
+    "public.source-code": ["public.plain-text"] [] [],
+    "public.swift-source": ["public.source-code"] ["swift"] [],
+    "public.image": ["public.data", "public.content"] [] [],
+    "public.jpeg": ["public.image"] ["jpg", "jpeg"] ["image/jpeg"],
+    "public.png": ["public.image"] ["png"] ["image/png"],
+    "com.compuserve.gif": ["public.image"] ["gif"] ["image/gif"],
+    "public.heic": ["public.image"] ["heic"] ["image/heic"],
+    "public.tiff": ["public.image"] ["tiff", "tif"] ["image/tiff"],
+    "com.microsoft.bmp": ["public.image"] ["bmp"] ["image/bmp"],
+    "org.webmproject.webp": ["public.image"] ["webp"] ["image/webp"],
+    "public.svg-image": ["public.image"] ["svg"] ["image/svg+xml"],
+    "public.audiovisual-content": ["public.data", "public.content"] [] [],
+    "public.audio": ["public.audiovisual-content"] [] [],
+    "public.mp3": ["public.audio"] ["mp3"] ["audio/mpeg"],
+    "public.mpeg-4-audio": ["public.audio"] ["m4a"] ["audio/mp4"],
+    "com.microsoft.waveform-audio": ["public.audio"] ["wav"] ["audio/wav", "audio/x-wav"],
+    "public.aiff-audio": ["public.audio"] ["aiff", "aif"] ["audio/aiff"],
+    "org.xiph.flac": ["public.audio"] ["flac"] ["audio/flac"],
+    "public.movie": ["public.audiovisual-content"] [] [],
+    "public.video": ["public.movie"] [] [],
+    "public.mpeg-4": ["public.movie"] ["mp4"] ["video/mp4"],
+    "com.apple.quicktime-movie": ["public.movie"] ["mov"] ["video/quicktime"],
+    "com.adobe.pdf": ["public.data", "public.composite-content"] ["pdf"] ["application/pdf"],
+    "public.archive": ["public.data"] [] [],
+    "public.zip-archive": ["public.archive"] ["zip"] ["application/zip"],
+    "org.gnu.gnu-zip-archive": ["public.archive"] ["gz"] ["application/gzip"],
+    "public.tar-archive": ["public.archive"] ["tar"] ["application/x-tar"],
+    "public.spreadsheet": ["public.content"] [] [],
+    "public.presentation": ["public.composite-content"] [] [],
+    "org.openxmlformats.wordprocessingml.document": ["public.data", "public.composite-content"] ["docx"] ["application/vnd.openxmlformats-officedocument.wordprocessingml.document"],
+    "org.openxmlformats.spreadsheetml.sheet": ["public.data", "public.spreadsheet"] ["xlsx"] ["application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"],
+    "org.openxmlformats.presentationml.presentation": ["public.data", "public.presentation"] ["pptx"] ["application/vnd.openxmlformats-officedocument.presentationml.presentation"],
+};
+
+fn info(identifier: &str) -> Option<&'static TypeInfo> {
+    TYPES.iter().find(|t| t.identifier == identifier)
+}
+
+/// A uniform type, e.g. `com.adobe.pdf`, to filter the picked documents.
+///
+/// It could be built from a filename extension, a MIME type or a type
+/// identifier, and conforms to the more general types of it, so that
+/// [`ContentType::IMAGE`] accepts any image.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ContentType(Cow<'static, str>);
+
+impl ContentType {
+    /// Any item, including folders.
+    pub const ITEM: Self = Self(Cow::Borrowed("public.item"));
+    /// Any content.
+    pub const CONTENT: Self = Self(Cow::Borrowed("public.content"));
+    /// Any file.
+    pub const DATA: Self = Self(Cow::Borrowed("public.data"));
+    /// Folders.
+    pub const FOLDER: Self = Self(Cow::Borrowed("public.folder"));
+    /// Any text.
+    pub const TEXT: Self = Self(Cow::Borrowed("public.text"));
+    /// Plain text.
+    pub const PLAIN_TEXT: Self = Self(Cow::Borrowed("public.plain-text"));
+    /// Any image.
+    pub const IMAGE: Self = Self(Cow::Borrowed("public.image"));
+    /// Any audio.
+    pub const AUDIO: Self = Self(Cow::Borrowed("public.audio"));
+    /// Any movie, with or without audio.
+    pub const MOVIE: Self = Self(Cow::Borrowed("public.movie"));
+    /// PDF documents.
+    pub const PDF: Self = Self(Cow::Borrowed("com.adobe.pdf"));
+    /// Any archive.
+    pub const ARCHIVE: Self = Self(Cow::Borrowed("public.archive"));
+
+    pub(crate) const fn builtin(identifier: &'static str) -> Self {
+        Self(Cow::Borrowed(identifier))
+    }
+
+    /// The type of a filename extension, with or without the leading dot.
+    ///
+    /// An extension which isn't in the built-in table is an
+    /// [`PickError::UnsupportedType`].
+    pub fn from_extension(extension: &str) -> Result<Self, PickError> {
+        let ext = extension.strip_prefix('.').unwrap_or(extension);
+        TYPES
+            .iter()
+            .find(|t| t.extensions.iter().any(|e| e.eq_ignore_ascii_case(ext)))
+            .map(|t| Self(Cow::Borrowed(t.identifier)))
+            .ok_or_else(|| PickError::UnsupportedType(extension.to_string()))
+    }
+
+    /// The type of a MIME type, e.g. `image/png`.
+    ///
+    /// A MIME type which isn't in the built-in table is an
+    /// [`PickError::UnsupportedType`].
+    pub fn from_mime_type(mime_type: &str) -> Result<Self, PickError> {
+        let essence = mime_type.split(';').next().unwrap_or_default().trim();
+        TYPES
+            .iter()
+            .find(|t| t.mime_types.iter().any(|m| m.eq_ignore_ascii_case(essence)))
+            .map(|t| Self(Cow::Borrowed(t.identifier)))
+            .ok_or_else(|| PickError::UnsupportedType(mime_type.to_string()))
+    }
+
+    /// The type of an identifier, e.g. `public.image`.
+    ///
+    /// Identifiers out of the built-in table are accepted, as apps declare
+    /// their own types, but they must be reverse-DNS strings of ASCII
+    /// alphanumerics, hyphens and dots. Those which the system doesn't know
+    /// fail the presentation with [`PickError::UnsupportedType`].
+    pub fn from_identifier(identifier: &str) -> Result<Self, PickError> {
+        if let Some(info) = info(identifier) {
+            return Ok(Self(Cow::Borrowed(info.identifier)));
+        }
+        let valid = identifier.split('.').count() >= 2
+            && identifier.split('.').all(|part| {
+                !part.is_empty() && part.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-')
+            });
+        if valid {
+            Ok(Self(Cow::Owned(identifier.to_string())))
+        } else {
+            Err(PickError::UnsupportedType(identifier.to_string()))
+        }
+    }
+
+    /// The type identifier.
+    pub fn identifier(&self) -> &str {
+        &self.0
+    }
+
+    /// The preferred filename extension.
+    pub fn preferred_extension(&self) -> Option<&'static str> {
+        info(&self.0).and_then(|t| t.extensions.first().copied())
+    }
+
+    /// The preferred MIME type.
+    pub fn preferred_mime_type(&self) -> Option<&'static str> {
+        info(&self.0).and_then(|t| t.mime_types.first().copied())
+    }
+
+    /// Whether the type is `other` or a more specific type of it. Types out
+    /// of the built-in table only conform to themselves.
+    pub fn conforms_to(&self, other: &ContentType) -> bool {
+        fn conforms(identifier: &str, other: &str) -> bool {
+            identifier == other
+                || info(identifier)
+                    .is_some_and(|t| t.conforms_to.iter().any(|p| conforms(p, other)))
+        }
+        conforms(&self.0, &other.0)
+    }
+}
+
+impl Display for ContentType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for ContentType {
+    type Err = PickError;
+
+    /// Parse a MIME type if it has a `/`, an extension if it starts with a
+    /// `.`, or else an identifier.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.contains('/') {
+            Self::from_mime_type(s)
+        } else if s.starts_with('.') {
+            Self::from_extension(s)
+        } else {
+            Self::from_identifier(s)
+        }
+    }
+}
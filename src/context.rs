@@ -0,0 +1,88 @@
+use crate::{
+    backend::{PickMessageKind, RawPickError, RawPickEvent, RawPickedFile},
+    PickError, PickEvent, PickedFile,
+};
+use std::{
+    ffi::{c_char, c_void, CStr},
+    mem::size_of,
+};
+
+/// The receiver of the messages of one picker presentation.
+pub(crate) trait PickSink {
+    fn file(&mut self, file: PickedFile);
+
+    fn file_error(&mut self, error: PickError);
+
+    fn failed(&mut self, error: PickError);
+
+    fn cancelled(&mut self);
+
+    fn exported(&mut self, _url: String) {}
+
+    fn chunk(&mut self, _data: &[u8]) {}
+
+    fn event(&mut self, _event: PickEvent) {}
+
+    fn finished(self);
+}
+
+/// The context passed to the native side as `callback_data`.
+///
+/// It is created by [`CallbackContext::into_raw`], and freed exactly once when
+/// [`PickMessageKind::Finished`] is dispatched.
+pub(crate) struct CallbackContext<S: PickSink>(S);
+
+impl<S: PickSink> CallbackContext<S> {
+    pub fn into_raw(sink: S) -> *mut c_void {
+        Box::into_raw(Box::new(Self(sink))) as *mut c_void
+    }
+
+    /// # Safety
+    ///
+    /// `context` must be created by [`CallbackContext::into_raw`] with the same
+    /// sink type, and it must not be finished yet.
+    pub unsafe fn dispatch(
+        context: *mut c_void,
+        kind: PickMessageKind,
+        data: *const c_void,
+        len: usize,
+    ) {
+        let context = context as *mut Self;
+        match kind {
+            PickMessageKind::File => {
+                debug_assert_eq!(len, size_of::<RawPickedFile>());
+                let file = (*(data as *const RawPickedFile)).to_file();
+                (*context).0.file(file);
+            }
+            PickMessageKind::FileError => {
+                debug_assert_eq!(len, size_of::<RawPickError>());
+                let error = (*(data as *const RawPickError)).to_error();
+                (*context).0.file_error(error);
+            }
+            PickMessageKind::Failed => {
+                debug_assert_eq!(len, size_of::<RawPickError>());
+                let error = (*(data as *const RawPickError)).to_error();
+                (*context).0.failed(error);
+            }
+            PickMessageKind::Cancelled => (*context).0.cancelled(),
+            PickMessageKind::Exported => {
+                let url = CStr::from_ptr(data as *const c_char);
+                (*context).0.exported(url.to_string_lossy().into_owned());
+            }
+            PickMessageKind::Chunk => {
+                let data = if data.is_null() {
+                    &[]
+                } else {
+                    std::slice::from_raw_parts(data as *const u8, len)
+                };
+                (*context).0.chunk(data);
+            }
+            PickMessageKind::Event => {
+                debug_assert_eq!(len, size_of::<RawPickEvent>());
+                let event = (*(data as *const RawPickEvent)).to_event();
+                (*context).0.event(event);
+            }
+            PickMessageKind::Finished => Box::from_raw(context).0.finished(),
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use crate::{
+    backend::{PickMessageKind, WriteOperation},
+    context::{CallbackContext, PickSink},
+    picker::PickAccess,
+    FileMetadata, PickError, PickedFile,
+};
+use futures_channel::oneshot;
+use std::{
+    ffi::c_void,
+    future::Future,
+    io,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+
+type WriteSender = oneshot::Sender<Result<Option<FileMetadata>, PickError>>;
+
+/// Receives the metadata of the written file, or nothing for a deletion.
+struct WriteSink(Option<WriteSender>);
+
+impl WriteSink {
+    fn send(&mut self, res: Result<Option<FileMetadata>, PickError>) {
+        if let Some(sender) = self.0.take() {
+            sender.send(res).ok();
+        }
+    }
+}
+
+impl PickSink for WriteSink {
+    fn file(&mut self, file: PickedFile) {
+        self.send(Ok(Some(file.into_parts().0)));
+    }
+
+    fn file_error(&mut self, error: PickError) {
+        self.send(Err(error));
+    }
+
+    fn failed(&mut self, error: PickError) {
+        self.send(Err(error));
+    }
+
+    fn cancelled(&mut self) {
+        self.send(Err(PickError::Cancelled));
+    }
+
+    fn finished(mut self) {
+        self.send(Ok(None));
+    }
+}
+
+unsafe extern "C" fn write_closure(
+    kind: PickMessageKind,
+    data: *const c_void,
+    len: usize,
+    closure_data: *mut c_void,
+) {
+    CallbackContext::<WriteSink>::dispatch(closure_data, kind, data, len);
+}
+
+/// Change a picked document with coordination, keeping the access until it is
+/// done.
+pub(crate) fn write_with(
+    access: &Arc<PickAccess>,
+    operation: &WriteOperation<'_>,
+) -> impl Future<Output = Result<Option<FileMetadata>, PickError>> + Send + Sync {
+    let (tx, rx) = oneshot::channel();
+    unsafe {
+        access.backend.write(
+            operation,
+            write_closure,
+            CallbackContext::into_raw(WriteSink(Some(tx))),
+        );
+    }
+    let access = access.clone();
+    async move {
+        let res = rx.await.unwrap_or(Err(PickError::Cancelled));
+        drop(access);
+        res
+    }
+}
+
+/// Change a picked document, resulting in a file.
+pub(crate) fn write_file_with(
+    access: &Arc<PickAccess>,
+    operation: &WriteOperation<'_>,
+) -> impl Future<Output = Result<FileMetadata, PickError>> + Send + Sync {
+    let write = write_with(access, operation);
+    // A cancellation is reported on its own, and so a write finishing without
+    // the file is a failure.
+    async move {
+        write
+            .await?
+            .ok_or_else(|| io::Error::other("the write finished without the file").into())
+    }
+}
+
+/// The path of `name` in `dir`, which must be a plain file name so that it
+/// stays directly in `dir`.
+pub(crate) fn child_path(dir: &Path, name: &str) -> Result<PathBuf, PickError> {
+    let mut components = Path::new(name).components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(_)), None) => Ok(dir.join(name)),
+        _ => Err(not_file_name(name)),
+    }
+}
+
+/// The path of `name` in the directory of `path`, like [`child_path`].
+pub(crate) fn sibling_path(path: &Path, name: &str) -> Result<PathBuf, PickError> {
+    let parent = path.parent().ok_or_else(|| not_file_name(name))?;
+    child_path(parent, name)
+}
+
+fn not_file_name(name: &str) -> PickError {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("{name:?} isn't a file name"),
+    )
+    .into()
+}
@@ -0,0 +1,159 @@
+use crate::{backend::RequestId, ContentType};
+use std::{error::Error, fmt::Display, io};
+
+/// An `NSError` reported by the native side.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NativeError {
+    /// The error domain, e.g. `NSCocoaErrorDomain`.
+    pub domain: String,
+    /// The error code in the domain.
+    pub code: isize,
+    /// The localized description.
+    pub description: String,
+}
+
+impl Display for NativeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({} {})", self.description, self.domain, self.code)
+    }
+}
+
+impl Error for NativeError {}
+
+/// The error of a pick.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PickError {
+    /// The picker is cancelled by the user.
+    Cancelled,
+    /// The security-scoped access of the picked file is denied.
+    AccessDenied,
+    /// The coordinated reading or writing of the picked file failed.
+    CoordinationFailed(NativeError),
+    /// The content of the picked file couldn't be read.
+    ReadFailed(NativeError),
+    /// The picked file couldn't be written, moved or deleted.
+    WriteFailed(NativeError),
+    /// The requested type couldn't be resolved.
+    UnsupportedType(String),
+    /// There's no document picker on this platform.
+    UnsupportedPlatform,
+    /// There's no view controller to present the picker.
+    NoPresenter,
+    /// Another picker is presented, and the session rejects the request with
+    /// [`SessionPolicy::RejectBusy`](crate::backend::SessionPolicy::RejectBusy).
+    Busy {
+        /// The request of the presented picker.
+        current: RequestId,
+    },
+    /// The bookmark couldn't be resolved to a document.
+    BookmarkFailed(NativeError),
+    /// A glob pattern couldn't be parsed.
+    InvalidPattern(String),
+    /// The content of the picked file doesn't match the requested types.
+    TypeMismatch {
+        /// The file name.
+        name: String,
+        /// The type detected by the magic bytes.
+        detected: Option<ContentType>,
+    },
+    /// The picked file is larger than [`PickOptions::max_file_size`], and
+    /// isn't read.
+    ///
+    /// [`PickOptions::max_file_size`]: crate::PickOptions::max_file_size
+    FileTooLarge {
+        /// The file name.
+        name: String,
+        /// The file size in bytes.
+        size: u64,
+        /// The maximum size.
+        limit: u64,
+    },
+    /// The picked file doesn't fit in what remains of
+    /// [`PickOptions::byte_budget`], and isn't read.
+    ///
+    /// [`PickOptions::byte_budget`]: crate::PickOptions::byte_budget
+    BudgetExceeded {
+        /// The file name.
+        name: String,
+        /// The file size in bytes.
+        size: u64,
+        /// The remaining bytes of the budget.
+        remaining: u64,
+    },
+    /// The pick didn't complete within
+    /// [`PickOptions::timeout`](crate::PickOptions::timeout).
+    TimedOut,
+    /// A local file operation failed.
+    Io {
+        /// The kind of the underlying error.
+        kind: io::ErrorKind,
+        /// The message of the underlying error.
+        message: String,
+    },
+}
+
+impl From<io::Error> for PickError {
+    fn from(e: io::Error) -> Self {
+        Self::Io {
+            kind: e.kind(),
+            message: e.to_string(),
+        }
+    }
+}
+
+impl From<PickError> for io::Error {
+    fn from(e: PickError) -> Self {
+        match e {
+            PickError::Io { kind, message } => io::Error::new(kind, message),
+            e => io::Error::other(e),
+        }
+    }
+}
+
+impl Display for PickError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Cancelled => write!(f, "the picker is cancelled"),
+            Self::AccessDenied => write!(f, "the access to the file is denied"),
+            Self::CoordinationFailed(e) => write!(f, "failed to coordinate the access: {e}"),
+            Self::ReadFailed(e) => write!(f, "failed to read the file: {e}"),
+            Self::WriteFailed(e) => write!(f, "failed to write the file: {e}"),
+            Self::UnsupportedType(t) => write!(f, "unsupported type: {t}"),
+            Self::UnsupportedPlatform => write!(f, "the platform doesn't support document picker"),
+            Self::NoPresenter => write!(f, "no view controller to present the picker"),
+            Self::Busy { current } => write!(f, "another picker is presented for {current}"),
+            Self::BookmarkFailed(e) => write!(f, "failed to resolve the bookmark: {e}"),
+            Self::InvalidPattern(p) => write!(f, "invalid glob pattern: {p}"),
+            Self::TypeMismatch { name, detected } => match detected {
+                Some(t) => write!(f, "{name} is {t}, not of the requested types"),
+                None => write!(f, "{name} is not of the requested types"),
+            },
+            Self::FileTooLarge { name, size, limit } => {
+                write!(f, "{name} has {size} bytes, more than the limit of {limit}")
+            }
+            Self::BudgetExceeded {
+                name,
+                size,
+                remaining,
+            } => write!(
+                f,
+                "{name} has {size} bytes, more than the remaining {remaining}"
+            ),
+            Self::TimedOut => write!(f, "the pick timed out"),
+            Self::Io { message, .. } => write!(f, "IO error: {message}"),
+        }
+    }
+}
+
+impl Error for PickError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::CoordinationFailed(e)
+            | Self::ReadFailed(e)
+            | Self::WriteFailed(e)
+            | Self::BookmarkFailed(e) => Some(e),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use crate::{
+    backend::PickMessageKind,
+    context::{CallbackContext, PickSink},
+    PickError, PickedFile,
+};
+use futures_channel::mpsc;
+use futures_core::Stream;
+use std::{
+    ffi::c_void,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Something which happens during a pick, reported next to its result.
+///
+/// The events are reported by [`FilePicker::pick_file_with_events`] and
+/// [`FilePicker::pick_files_with_events`]. The file events are only reported
+/// for the files read at once, by their index in the selection.
+///
+/// [`FilePicker::pick_file_with_events`]: crate::FilePicker::pick_file_with_events
+/// [`FilePicker::pick_files_with_events`]: crate::FilePicker::pick_files_with_events
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PickEvent {
+    /// The picker appears.
+    Presented,
+    /// The picker goes away, before the picked files are read.
+    Dismissed,
+    /// A picked file starts being read, which may wait for it to download.
+    FileStarted {
+        /// The index of the file in the selection.
+        index: usize,
+        /// The file name.
+        name: String,
+    },
+    /// A part of a file is downloaded or read. It is only reported for files
+    /// of known size.
+    Progress {
+        /// The index of the file in the selection.
+        index: usize,
+        /// The bytes done so far.
+        completed: u64,
+        /// The file size.
+        total: u64,
+    },
+    /// A picked file is read, or failed to be.
+    FileFinished {
+        /// The index of the file in the selection.
+        index: usize,
+    },
+    /// The pick is done. It is always the last event.
+    Completed,
+}
+
+/// The events of one pick, ending after [`PickEvent::Completed`].
+#[derive(Debug)]
+pub struct PickEvents(mpsc::UnboundedReceiver<PickEvent>);
+
+impl PickEvents {
+    pub(crate) fn channel() -> (EventSender, Self) {
+        let (tx, rx) = mpsc::unbounded();
+        (tx, Self(rx))
+    }
+}
+
+impl Stream for PickEvents {
+    type Item = PickEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.0).poll_next(cx)
+    }
+}
+
+pub(crate) type EventSender = mpsc::UnboundedSender<PickEvent>;
+
+/// Reports the events of a picker, and the rest of its messages to `sink`.
+pub(crate) struct EventSink<S> {
+    pub sink: S,
+    pub events: EventSender,
+}
+
+impl<S: PickSink> PickSink for EventSink<S> {
+    fn file(&mut self, file: PickedFile) {
+        self.sink.file(file);
+    }
+
+    fn file_error(&mut self, error: PickError) {
+        self.sink.file_error(error);
+    }
+
+    fn failed(&mut self, error: PickError) {
+        self.sink.failed(error);
+    }
+
+    fn cancelled(&mut self) {
+        self.sink.cancelled();
+    }
+
+    fn exported(&mut self, url: String) {
+        self.sink.exported(url);
+    }
+
+    fn chunk(&mut self, data: &[u8]) {
+        self.sink.chunk(data);
+    }
+
+    fn event(&mut self, event: PickEvent) {
+        self.events.unbounded_send(event).ok();
+    }
+
+    fn finished(self) {
+        self.sink.finished();
+        self.events.unbounded_send(PickEvent::Completed).ok();
+    }
+}
+
+pub(crate) unsafe extern "C" fn event_closure<S: PickSink>(
+    kind: PickMessageKind,
+    data: *const c_void,
+    len: usize,
+    closure_data: *mut c_void,
+) {
+    CallbackContext::<EventSink<S>>::dispatch(closure_data, kind, data, len);
+}
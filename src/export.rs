@@ -0,0 +1,200 @@
+use crate::{
+    backend::{
+        default_backend, ExportRequest, PickMessageKind, PickerBackend, Presentation, RequestId,
+    },
+    context::{CallbackContext, PickSink},
+    PickError, PickedFile, Presenter,
+};
+use futures_channel::oneshot;
+use pin_project::pin_project;
+use std::{
+    ffi::c_void,
+    fs,
+    future::Future,
+    io,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::atomic::{AtomicUsize, Ordering},
+    task::{Context, Poll},
+};
+
+struct ExportSink {
+    sender: Option<oneshot::Sender<Result<Vec<String>, PickError>>>,
+    urls: Vec<String>,
+    error: Option<PickError>,
+}
+
+impl PickSink for ExportSink {
+    fn file(&mut self, _file: PickedFile) {}
+
+    fn file_error(&mut self, error: PickError) {
+        self.error.get_or_insert(error);
+    }
+
+    fn failed(&mut self, error: PickError) {
+        self.error.get_or_insert(error);
+    }
+
+    fn cancelled(&mut self) {
+        self.error.get_or_insert(PickError::Cancelled);
+    }
+
+    fn exported(&mut self, url: String) {
+        self.urls.push(url);
+    }
+
+    fn finished(self) {
+        if let Some(sender) = self.sender {
+            let res = match self.error {
+                Some(error) => Err(error),
+                None => Ok(self.urls),
+            };
+            sender.send(res).ok();
+        }
+    }
+}
+
+unsafe extern "C" fn export_closure(
+    kind: PickMessageKind,
+    data: *const c_void,
+    len: usize,
+    closure_data: *mut c_void,
+) {
+    CallbackContext::<ExportSink>::dispatch(closure_data, kind, data, len);
+}
+
+/// A temporary directory holding a file to save, removed on drop.
+struct StagingDir(PathBuf);
+
+impl StagingDir {
+    fn new() -> io::Result<Self> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let name = format!(
+            "file-picker-ios-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        );
+        let path = std::env::temp_dir().join(name);
+        fs::create_dir_all(&path)?;
+        Ok(Self(path))
+    }
+
+    fn stage(&self, bytes: &[u8], suggested_name: &str) -> io::Result<PathBuf> {
+        let name = Path::new(suggested_name)
+            .file_name()
+            .filter(|name| !name.is_empty())
+            .unwrap_or("Untitled".as_ref());
+        let path = self.0.join(name);
+        fs::write(&path, bytes)?;
+        Ok(path)
+    }
+}
+
+impl Drop for StagingDir {
+    fn drop(&mut self) {
+        fs::remove_dir_all(&self.0).ok();
+    }
+}
+
+fn export_impl(
+    backend: &dyn PickerBackend,
+    presenter: &Presenter,
+    paths: &[PathBuf],
+    staging: Option<StagingDir>,
+) -> ExportFuture {
+    let (tx, rx) = oneshot::channel();
+    let delegate = unsafe {
+        backend.present_export(
+            &ExportRequest {
+                id: RequestId::next(),
+                presenter,
+                paths,
+            },
+            export_closure,
+            CallbackContext::into_raw(ExportSink {
+                sender: Some(tx),
+                urls: vec![],
+                error: None,
+            }),
+        )
+    };
+    ExportFuture {
+        rx,
+        delegate,
+        _staging: staging,
+    }
+}
+
+#[pin_project]
+struct ExportFuture {
+    #[pin]
+    rx: oneshot::Receiver<Result<Vec<String>, PickError>>,
+    delegate: Presentation,
+    _staging: Option<StagingDir>,
+}
+
+impl Future for ExportFuture {
+    type Output = Result<Vec<String>, PickError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.project()
+            .rx
+            .poll(cx)
+            .map(|res| res.unwrap_or(Err(PickError::Cancelled)))
+    }
+}
+
+/// Let the user save `bytes` as a new document.
+///
+/// The bytes are staged in a temporary file named `suggested_name`, which is
+/// removed when the future completes or is dropped. It resolves to the URL of
+/// the saved document.
+pub fn save_file(
+    presenter: &Presenter,
+    bytes: &[u8],
+    suggested_name: &str,
+) -> impl Future<Output = Result<String, PickError>> + Send + Sync {
+    save_file_with(&*default_backend(), presenter, bytes, suggested_name)
+}
+
+/// Let the user save `bytes` as a new document with the specified backend.
+pub fn save_file_with(
+    backend: &dyn PickerBackend,
+    presenter: &Presenter,
+    bytes: &[u8],
+    suggested_name: &str,
+) -> impl Future<Output = Result<String, PickError>> + Send + Sync {
+    let staged = StagingDir::new().and_then(|staging| {
+        let path = staging.stage(bytes, suggested_name)?;
+        Ok((staging, path))
+    });
+    let f = staged.map(|(staging, path)| export_impl(backend, presenter, &[path], Some(staging)));
+    async move {
+        let mut urls = f?.await?;
+        // A cancellation is reported on its own, and so a save without a URL
+        // is a failure.
+        urls.pop()
+            .ok_or_else(|| io::Error::other("the document was saved without a URL").into())
+    }
+}
+
+/// Let the user export local files.
+///
+/// The files are copied to the destination. It resolves to the URLs of the
+/// exported documents.
+pub fn export_files(
+    presenter: &Presenter,
+    paths: &[PathBuf],
+) -> impl Future<Output = Result<Vec<String>, PickError>> + Send + Sync {
+    export_files_with(&*default_backend(), presenter, paths)
+}
+
+/// Let the user export local files with the specified backend.
+pub fn export_files_with(
+    backend: &dyn PickerBackend,
+    presenter: &Presenter,
+    paths: &[PathBuf],
+) -> impl Future<Output = Result<Vec<String>, PickError>> + Send + Sync {
+    export_impl(backend, presenter, paths, None)
+}
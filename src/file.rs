@@ -0,0 +1,271 @@
+use crate::{backend::RequestId, Bookmark, ContentType, Sniffed};
+use bytes::Bytes;
+use stable_deref_trait::{CloneStableDeref, StableDeref};
+use std::{
+    ffi::c_void,
+    fmt::Debug,
+    ops::{Bound, Deref, RangeBounds},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+/// The buffer of a [`FileHandle`].
+enum Storage {
+    Shared(Arc<[u8]>),
+    /// A buffer owned by the native side, e.g. a retained, possibly mapped
+    /// `NSData`.
+    Native {
+        data: *const u8,
+        len: usize,
+        owner: *mut c_void,
+        release: unsafe extern "C" fn(*mut c_void),
+    },
+}
+
+// SAFETY: the native buffers are immutable, and could be released on any
+// thread.
+unsafe impl Send for Storage {}
+unsafe impl Sync for Storage {}
+
+impl Storage {
+    fn as_slice(&self) -> &[u8] {
+        match self {
+            Self::Shared(data) => data,
+            Self::Native { data, len: 0, .. } if data.is_null() => &[],
+            Self::Native { data, len, .. } => unsafe { std::slice::from_raw_parts(*data, *len) },
+        }
+    }
+}
+
+impl Drop for Storage {
+    fn drop(&mut self) {
+        if let Self::Native { owner, release, .. } = self {
+            unsafe { release(*owner) }
+        }
+    }
+}
+
+/// A file handle which contains the content of the file.
+///
+/// The content isn't copied out of the native buffer it is read into, and
+/// cloning or slicing a handle shares the same buffer.
+#[derive(Clone)]
+pub struct FileHandle {
+    storage: Arc<Storage>,
+    start: usize,
+    end: usize,
+}
+
+impl FileHandle {
+    /// Take the ownership of a native buffer.
+    ///
+    /// # Safety
+    ///
+    /// `data` must be valid for `len` bytes and not change until
+    /// `release(owner)` is called, which happens exactly once when the last
+    /// handle sharing it drops, on any thread.
+    pub(crate) unsafe fn from_native(
+        data: *const u8,
+        len: usize,
+        owner: *mut c_void,
+        release: unsafe extern "C" fn(*mut c_void),
+    ) -> Self {
+        Self {
+            storage: Arc::new(Storage::Native {
+                data,
+                len,
+                owner,
+                release,
+            }),
+            start: 0,
+            end: len,
+        }
+    }
+
+    /// A handle of a part of the content, sharing the same buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bounds.
+    pub fn slice(&self, range: impl RangeBounds<usize>) -> Self {
+        let start = match range.start_bound() {
+            Bound::Included(&n) => Some(n),
+            Bound::Excluded(&n) => n.checked_add(1),
+            Bound::Unbounded => Some(0),
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&n) => n.checked_add(1),
+            Bound::Excluded(&n) => Some(n),
+            Bound::Unbounded => Some(self.len()),
+        };
+        let (start, end) = match (start, end) {
+            (Some(start), Some(end)) if start <= end && end <= self.len() => (start, end),
+            _ => panic!(
+                "range {:?} out of bounds of {}",
+                (range.start_bound(), range.end_bound()),
+                self.len()
+            ),
+        };
+        Self {
+            storage: self.storage.clone(),
+            start: self.start + start,
+            end: self.start + end,
+        }
+    }
+}
+
+impl Debug for FileHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FileHandle")
+            .field("len", &self.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Deref for FileHandle {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        &self.storage.as_slice()[self.start..self.end]
+    }
+}
+
+impl AsRef<[u8]> for FileHandle {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl From<Arc<[u8]>> for FileHandle {
+    fn from(data: Arc<[u8]>) -> Self {
+        Self {
+            end: data.len(),
+            storage: Arc::new(Storage::Shared(data)),
+            start: 0,
+        }
+    }
+}
+
+impl From<Vec<u8>> for FileHandle {
+    fn from(data: Vec<u8>) -> Self {
+        Arc::<[u8]>::from(data).into()
+    }
+}
+
+impl From<FileHandle> for Bytes {
+    fn from(handle: FileHandle) -> Self {
+        Bytes::from_owner(handle)
+    }
+}
+
+// SAFETY: the buffer is never moved or mutated while any handle shares it.
+unsafe impl StableDeref for FileHandle {}
+unsafe impl CloneStableDeref for FileHandle {}
+
+/// The metadata of a picked file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct FileMetadata {
+    /// The file name.
+    pub name: String,
+    /// The URL of the picked document.
+    pub url: String,
+    /// The local path of the picked document.
+    pub path: Option<PathBuf>,
+    /// The bookmark to reopen the document later, if requested by
+    /// [`PickOptions::bookmarks`](crate::PickOptions::bookmarks).
+    pub bookmark: Option<Bookmark>,
+    /// The identifier of the resolved UTType, e.g. `com.adobe.pdf`.
+    pub content_type: Option<String>,
+    /// The file size in bytes.
+    pub size: Option<u64>,
+    /// The creation date.
+    pub created: Option<SystemTime>,
+    /// The last modification date.
+    pub modified: Option<SystemTime>,
+    /// The name of the providing app or location, e.g. `iCloud Drive`.
+    pub provider: Option<String>,
+    /// The result of the content check, if requested by
+    /// [`PickOptions::sniff`](crate::PickOptions::sniff).
+    pub sniffed: Option<Sniffed>,
+    /// The request of the picker which picked the file.
+    pub request: Option<RequestId>,
+}
+
+impl FileMetadata {
+    /// Create metadata with the file name.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    /// The filename extension.
+    pub fn extension(&self) -> Option<&str> {
+        Path::new(&self.name).extension().and_then(|e| e.to_str())
+    }
+
+    /// Whether the file conforms to `content_type`, judged by the resolved
+    /// UTType, or else by the extension.
+    pub fn conforms_to(&self, content_type: &ContentType) -> bool {
+        let own = match &self.content_type {
+            Some(identifier) => ContentType::from_identifier(identifier),
+            None => ContentType::from_extension(self.extension().unwrap_or_default()),
+        };
+        own.is_ok_and(|own| own.conforms_to(content_type))
+    }
+}
+
+pub(crate) fn system_time_from_secs(secs: f64) -> Option<SystemTime> {
+    let duration = Duration::try_from_secs_f64(secs.abs()).ok()?;
+    if secs >= 0.0 {
+        SystemTime::UNIX_EPOCH.checked_add(duration)
+    } else {
+        SystemTime::UNIX_EPOCH.checked_sub(duration)
+    }
+}
+
+pub(crate) fn system_time_to_secs(time: SystemTime) -> f64 {
+    match time.duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(d) => d.as_secs_f64(),
+        Err(e) => -e.duration().as_secs_f64(),
+    }
+}
+
+/// A picked file with its metadata and content.
+#[derive(Debug, Clone)]
+pub struct PickedFile {
+    metadata: FileMetadata,
+    handle: FileHandle,
+}
+
+impl PickedFile {
+    pub(crate) fn new(metadata: FileMetadata, handle: FileHandle) -> Self {
+        Self { metadata, handle }
+    }
+
+    /// The metadata of the file.
+    pub fn metadata(&self) -> &FileMetadata {
+        &self.metadata
+    }
+
+    /// The content of the file.
+    pub fn handle(&self) -> &FileHandle {
+        &self.handle
+    }
+
+    /// Split into the metadata and the content.
+    pub fn into_parts(self) -> (FileMetadata, FileHandle) {
+        (self.metadata, self.handle)
+    }
+}
+
+impl Deref for PickedFile {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        &self.handle
+    }
+}
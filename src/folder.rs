@@ -0,0 +1,487 @@
+use crate::{
+    backend::{default_backend, PickerBackend, WriteOperation},
+    edit::{child_path, sibling_path, write_file_with, write_with},
+    picker::{pick_with_access, PickAccess},
+    FileMetadata, PickError, PickOptions, PickedFile, PresentationStyle, Presenter,
+};
+use glob::{MatchOptions, Pattern};
+use std::{
+    fmt::Debug,
+    future::Future,
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use walkdir::WalkDir;
+
+/// How symbolic links inside a picked folder are enumerated.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SymlinkPolicy {
+    /// Leave out the links.
+    #[default]
+    Skip,
+    /// Report the links themselves, without following them.
+    Include,
+    /// Follow the links, as if the targets were in the folder.
+    Follow,
+}
+
+/// The kind of a [`FolderEntry`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    /// A regular file.
+    File,
+    /// A directory.
+    Dir,
+    /// A symbolic link, reported with [`SymlinkPolicy::Include`].
+    Symlink,
+}
+
+/// A picker of one folder, which is enumerated recursively.
+///
+/// ```no_run
+/// # use file_picker_ios::FolderPicker;
+/// # async fn f(presenter: &file_picker_ios::Presenter) {
+/// let folder = FolderPicker::new()
+///     .include("**/*.md")
+///     .exclude(".git")
+///     .pick_folder(presenter)
+///     .await
+///     .unwrap();
+/// for entry in folder.entries() {
+///     let contents = entry.unwrap().read().await;
+/// }
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct FolderPicker {
+    options: PickOptions,
+    include: Vec<String>,
+    exclude: Vec<String>,
+    max_depth: Option<usize>,
+    symlinks: SymlinkPolicy,
+    backend: Option<Arc<dyn PickerBackend>>,
+}
+
+impl FolderPicker {
+    /// Create a picker with the default options.
+    pub fn new() -> Self {
+        Self {
+            options: PickOptions {
+                allow_multiple: false,
+                folder: true,
+                ..PickOptions::default()
+            },
+            ..Self::default()
+        }
+    }
+
+    /// Set the directory shown when the picker appears.
+    pub fn initial_directory(mut self, path: impl Into<PathBuf>) -> Self {
+        self.options.initial_directory = Some(path.into());
+        self
+    }
+
+    /// Set the modal presentation style.
+    pub fn presentation_style(mut self, style: PresentationStyle) -> Self {
+        self.options.presentation_style = style;
+        self
+    }
+
+    /// Add a glob pattern of the entries to enumerate, matched against the
+    /// path relative to the folder. If there's none, every entry is
+    /// enumerated.
+    ///
+    /// A directory which doesn't match is still descended into.
+    pub fn include(mut self, pattern: impl Into<String>) -> Self {
+        self.include.push(pattern.into());
+        self
+    }
+
+    /// Add a glob pattern of the entries to leave out, matched against the
+    /// path relative to the folder. A matching directory isn't descended into.
+    pub fn exclude(mut self, pattern: impl Into<String>) -> Self {
+        self.exclude.push(pattern.into());
+        self
+    }
+
+    /// Set the maximum depth to enumerate. The entries directly in the folder
+    /// have depth 1.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Set how symbolic links are enumerated. They are skipped by default.
+    pub fn symlinks(mut self, policy: SymlinkPolicy) -> Self {
+        self.symlinks = policy;
+        self
+    }
+
+    /// Set the backend presenting the picker. It is [`default_backend`] by
+    /// default.
+    pub fn backend(mut self, backend: Arc<dyn PickerBackend>) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+
+    /// Pick one folder.
+    ///
+    /// The security-scoped access to the folder is kept until the returned
+    /// folder and all of its entries drop.
+    pub fn pick_folder(
+        &self,
+        presenter: &Presenter,
+    ) -> impl Future<Output = Result<PickedFolder, PickError>> + Send + Sync {
+        let backend = self.backend.clone().unwrap_or_else(default_backend);
+        let filter = self.filter();
+        let pick = filter
+            .is_ok()
+            .then(|| pick_with_access(backend, presenter, &self.options));
+        async move {
+            let filter = filter?;
+            let (file, access) = pick.unwrap().await?;
+            let (metadata, _) = file.into_parts();
+            let root = metadata.path.clone().ok_or_else(|| {
+                io::Error::new(io::ErrorKind::NotFound, "the folder has no local path")
+            })?;
+            Ok(PickedFolder {
+                metadata,
+                root,
+                filter: Arc::new(filter),
+                access,
+            })
+        }
+    }
+
+    fn filter(&self) -> Result<EntryFilter, PickError> {
+        let compile = |patterns: &[String]| {
+            patterns
+                .iter()
+                .map(|p| Pattern::new(p).map_err(|_| PickError::InvalidPattern(p.clone())))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        Ok(EntryFilter {
+            include: compile(&self.include)?,
+            exclude: compile(&self.exclude)?,
+            max_depth: self.max_depth,
+            symlinks: self.symlinks,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct EntryFilter {
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+    max_depth: Option<usize>,
+    symlinks: SymlinkPolicy,
+}
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+impl EntryFilter {
+    fn excludes(&self, path: &Path) -> bool {
+        self.exclude
+            .iter()
+            .any(|p| p.matches_path_with(path, MATCH_OPTIONS))
+    }
+
+    fn includes(&self, path: &Path) -> bool {
+        self.include.is_empty()
+            || self
+                .include
+                .iter()
+                .any(|p| p.matches_path_with(path, MATCH_OPTIONS))
+    }
+}
+
+/// A picked folder.
+#[derive(Clone)]
+pub struct PickedFolder {
+    metadata: FileMetadata,
+    root: PathBuf,
+    filter: Arc<EntryFilter>,
+    access: Arc<PickAccess>,
+}
+
+impl Debug for PickedFolder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PickedFolder")
+            .field("metadata", &self.metadata)
+            .field("root", &self.root)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PickedFolder {
+    /// The metadata of the folder.
+    pub fn metadata(&self) -> &FileMetadata {
+        &self.metadata
+    }
+
+    /// The local path of the folder.
+    pub fn path(&self) -> &Path {
+        &self.root
+    }
+
+    /// Create a file directly in the folder with coordination, failing if it
+    /// exists.
+    pub fn create_file(
+        &self,
+        name: &str,
+        data: &[u8],
+    ) -> impl Future<Output = Result<FolderEntry, PickError>> + Send + Sync {
+        let created = child_path(&self.root, name).map(|path| FolderEntry {
+            relative_path: PathBuf::from(name),
+            path,
+            kind: EntryKind::File,
+            size: Some(data.len() as u64),
+            access: self.access.clone(),
+        });
+        create_entry(created, data)
+    }
+
+    /// Enumerate the folder recursively.
+    ///
+    /// The entries of a directory are sorted by name, and each directory is
+    /// followed by its own entries. An entry which couldn't be enumerated
+    /// yields an error, and the enumeration goes on.
+    pub fn entries(&self) -> FolderEntries {
+        let mut walk = WalkDir::new(&self.root)
+            .min_depth(1)
+            .follow_links(self.filter.symlinks == SymlinkPolicy::Follow)
+            .sort_by_file_name();
+        if let Some(depth) = self.filter.max_depth {
+            walk = walk.max_depth(depth);
+        }
+        FolderEntries {
+            root: self.root.clone(),
+            filter: self.filter.clone(),
+            access: self.access.clone(),
+            walk: walk.into_iter(),
+        }
+    }
+}
+
+/// The iterator of the entries in a [`PickedFolder`].
+pub struct FolderEntries {
+    root: PathBuf,
+    filter: Arc<EntryFilter>,
+    access: Arc<PickAccess>,
+    walk: walkdir::IntoIter,
+}
+
+impl Debug for FolderEntries {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FolderEntries")
+            .field("root", &self.root)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Iterator for FolderEntries {
+    type Item = Result<FolderEntry, PickError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let entry = match self.walk.next()? {
+                Ok(entry) => entry,
+                Err(e) => return Some(Err(io::Error::from(e).into())),
+            };
+            let relative_path = entry
+                .path()
+                .strip_prefix(&self.root)
+                .unwrap_or(entry.path())
+                .to_path_buf();
+            let file_type = entry.file_type();
+            if self.filter.excludes(&relative_path) {
+                if file_type.is_dir() {
+                    self.walk.skip_current_dir();
+                }
+                continue;
+            }
+            let kind = if file_type.is_symlink() {
+                if self.filter.symlinks == SymlinkPolicy::Skip {
+                    continue;
+                }
+                EntryKind::Symlink
+            } else if file_type.is_dir() {
+                EntryKind::Dir
+            } else {
+                EntryKind::File
+            };
+            if !self.filter.includes(&relative_path) {
+                continue;
+            }
+            let size = match kind {
+                EntryKind::File => entry.metadata().ok().map(|m| m.len()),
+                _ => None,
+            };
+            return Some(Ok(FolderEntry {
+                relative_path,
+                path: entry.into_path(),
+                kind,
+                size,
+                access: self.access.clone(),
+            }));
+        }
+    }
+}
+
+/// An entry in a [`PickedFolder`].
+///
+/// Its contents are only read on [`FolderEntry::read`].
+#[derive(Clone)]
+pub struct FolderEntry {
+    relative_path: PathBuf,
+    path: PathBuf,
+    kind: EntryKind,
+    size: Option<u64>,
+    access: Arc<PickAccess>,
+}
+
+impl Debug for FolderEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FolderEntry")
+            .field("relative_path", &self.relative_path)
+            .field("kind", &self.kind)
+            .field("size", &self.size)
+            .finish_non_exhaustive()
+    }
+}
+
+impl FolderEntry {
+    /// The path relative to the folder.
+    pub fn relative_path(&self) -> &Path {
+        &self.relative_path
+    }
+
+    /// The local path.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The kind of the entry.
+    pub fn kind(&self) -> EntryKind {
+        self.kind
+    }
+
+    /// The file size in bytes. It is only known for files.
+    pub fn size(&self) -> Option<u64> {
+        self.size
+    }
+
+    /// Read the file with coordination.
+    pub fn read(&self) -> impl Future<Output = Result<PickedFile, PickError>> + Send + Sync {
+        self.access.read_file(&self.path)
+    }
+
+    /// Overwrite the file in place with coordination, returning its new
+    /// metadata.
+    pub fn write_all(
+        &self,
+        data: &[u8],
+    ) -> impl Future<Output = Result<FileMetadata, PickError>> + Send + Sync {
+        let operation = WriteOperation::Write {
+            path: &self.path,
+            data,
+        };
+        write_file_with(&self.access, &operation)
+    }
+
+    /// Replace the file atomically with coordination, returning its new
+    /// metadata.
+    pub fn replace(
+        &self,
+        data: &[u8],
+    ) -> impl Future<Output = Result<FileMetadata, PickError>> + Send + Sync {
+        let operation = WriteOperation::Replace {
+            path: &self.path,
+            data,
+        };
+        write_file_with(&self.access, &operation)
+    }
+
+    /// Rename the entry in its directory with coordination, failing if the
+    /// name is taken.
+    ///
+    /// `name` must be a file name, without any separator.
+    pub fn rename(
+        &self,
+        name: &str,
+    ) -> impl Future<Output = Result<FolderEntry, PickError>> + Send + Sync {
+        let renamed = sibling_path(&self.path, name).map(|path| FolderEntry {
+            relative_path: self.relative_path.with_file_name(name),
+            path,
+            ..self.clone()
+        });
+        let write = renamed.as_ref().ok().map(|renamed| {
+            let operation = WriteOperation::Move {
+                from: &self.path,
+                to: &renamed.path,
+            };
+            write_with(&self.access, &operation)
+        });
+        async move {
+            let renamed = renamed?;
+            write.unwrap().await?;
+            Ok(renamed)
+        }
+    }
+
+    /// Delete the entry with coordination, with its contents if it is a
+    /// directory.
+    pub fn delete(&self) -> impl Future<Output = Result<(), PickError>> + Send + Sync {
+        let write = write_with(&self.access, &WriteOperation::Delete { path: &self.path });
+        async move {
+            write.await?;
+            Ok(())
+        }
+    }
+
+    /// Create a file next to the entry with coordination, failing if it
+    /// exists.
+    ///
+    /// `name` must be a file name, without any separator.
+    pub fn create_sibling(
+        &self,
+        name: &str,
+        data: &[u8],
+    ) -> impl Future<Output = Result<FolderEntry, PickError>> + Send + Sync {
+        let created = sibling_path(&self.path, name).map(|path| FolderEntry {
+            relative_path: self.relative_path.with_file_name(name),
+            path,
+            kind: EntryKind::File,
+            size: Some(data.len() as u64),
+            access: self.access.clone(),
+        });
+        create_entry(created, data)
+    }
+}
+
+/// Create the file of the entry with `data`.
+fn create_entry(
+    created: Result<FolderEntry, PickError>,
+    data: &[u8],
+) -> impl Future<Output = Result<FolderEntry, PickError>> + Send + Sync {
+    let write = created.as_ref().ok().map(|created| {
+        let operation = WriteOperation::Create {
+            path: &created.path,
+            data,
+        };
+        write_file_with(&created.access, &operation)
+    });
+    async move {
+        let created = created?;
+        let metadata = write.unwrap().await?;
+        Ok(FolderEntry {
+            size: metadata.size,
+            ..created
+        })
+    }
+}
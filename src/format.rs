@@ -0,0 +1,125 @@
+//! The framing of the small files this crate persists.
+//!
+//! A file starts with a 4-byte magic and a little-endian `u32` format version,
+//! followed by little-endian integers and byte strings prefixed by their `u32`
+//! lengths. Times are the `u64` seconds and the `u32` nanoseconds since the
+//! Unix epoch. Files of another version are rejected rather than misread.
+
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+pub(crate) struct Encoder(Vec<u8>);
+
+impl Encoder {
+    pub fn new(magic: &[u8; 4], version: u32) -> Self {
+        let mut encoder = Self(magic.to_vec());
+        encoder.u32(version);
+        encoder
+    }
+
+    pub fn u32(&mut self, n: u32) {
+        self.0.extend_from_slice(&n.to_le_bytes());
+    }
+
+    pub fn u64(&mut self, n: u64) {
+        self.0.extend_from_slice(&n.to_le_bytes());
+    }
+
+    pub fn bytes(&mut self, bytes: &[u8]) {
+        self.u32(bytes.len() as u32);
+        self.0.extend_from_slice(bytes);
+    }
+
+    pub fn time(&mut self, time: SystemTime) {
+        let since = time
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+        self.u64(since.as_secs());
+        self.u32(since.subsec_nanos());
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+pub(crate) struct Decoder<'a>(&'a [u8]);
+
+impl<'a> Decoder<'a> {
+    /// Check the magic and the version of `data`. `what` names the file in
+    /// the errors.
+    pub fn new(data: &'a [u8], magic: &[u8; 4], version: u32, what: &str) -> io::Result<Self> {
+        let mut decoder = Self(data);
+        if decoder.take(4)? != magic {
+            return Err(invalid_data(format!("not a {what}")));
+        }
+        let found = decoder.u32()?;
+        if found != version {
+            return Err(invalid_data(format!("unsupported {what} version {found}")));
+        }
+        Ok(decoder)
+    }
+
+    fn take(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        if self.0.len() < len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(bytes)
+    }
+
+    pub fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    pub fn u64(&mut self) -> io::Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    pub fn bytes(&mut self) -> io::Result<&'a [u8]> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+
+    pub fn string(&mut self) -> io::Result<String> {
+        String::from_utf8(self.bytes()?.to_vec()).map_err(|e| invalid_data(e.to_string()))
+    }
+
+    pub fn time(&mut self) -> io::Result<SystemTime> {
+        let secs = self.u64()?;
+        let nanos = self.u32()?;
+        if nanos >= 1_000_000_000 {
+            return Err(invalid_data(format!("invalid nanoseconds {nanos}")));
+        }
+        SystemTime::UNIX_EPOCH
+            .checked_add(Duration::new(secs, nanos))
+            .ok_or_else(|| invalid_data(format!("time out of range: {secs} seconds")))
+    }
+}
+
+/// Write `data` to a temporary file next to `path`, and rename it over `path`,
+/// so that `path` always has either the old or the new content.
+pub(crate) fn write_atomically(path: &Path, data: &[u8]) -> io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(format!(".{}.tmp", std::process::id()));
+    let res = fs::File::create(&tmp).and_then(|mut file| {
+        file.write_all(data)?;
+        file.sync_all()
+    });
+    match res.and_then(|_| fs::rename(&tmp, path)) {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            fs::remove_file(&tmp).ok();
+            Err(e)
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use crate::{
+    format::{write_atomically, Decoder, Encoder},
+    PickError, PickedFile,
+};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+use unicode_normalization::UnicodeNormalization;
+
+const MANIFEST: &str = ".import-manifest";
+const MAGIC: &[u8; 4] = b"FPIM";
+const VERSION: u32 = 1;
+
+/// A document imported by [`ImportManager`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct ImportedItem {
+    /// The file name in the import directory.
+    pub name: String,
+    /// The local path in the import directory.
+    pub path: PathBuf,
+    /// The name of the picked document.
+    pub original_name: String,
+    /// The URL of the picked document.
+    pub url: String,
+    /// The file size in bytes.
+    pub size: u64,
+    /// When the document is imported.
+    pub imported: SystemTime,
+}
+
+/// Imports picked documents into a directory owned by the app.
+///
+/// Every document gets a file of its own, named after the document with the
+/// Unicode filename normalized to NFC, and a ` (1)`-like suffix if the name is
+/// taken. The files and the manifest of imported items are written
+/// atomically, so that an interrupted import leaves no partial file behind.
+///
+/// Use [`FilePicker::import_files`](crate::FilePicker::import_files) to pick
+/// and import documents at once.
+#[derive(Debug, Clone)]
+pub struct ImportManager {
+    dir: PathBuf,
+    items: Vec<ImportedItem>,
+}
+
+impl ImportManager {
+    /// Open the import directory, creating it if missing.
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self, PickError> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        let items = match fs::read(dir.join(MANIFEST)) {
+            Ok(data) => decode(&dir, &data)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => vec![],
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self { dir, items })
+    }
+
+    /// The import directory.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The imported items, in the order of import.
+    pub fn items(&self) -> &[ImportedItem] {
+        &self.items
+    }
+
+    /// Import a picked file.
+    pub fn import(&mut self, file: &PickedFile) -> Result<ImportedItem, PickError> {
+        let metadata = file.metadata();
+        self.import_contents(&metadata.name, &metadata.url, &file[..])
+    }
+
+    /// Import `contents` as a document named `name`, picked from `url`.
+    pub fn import_contents(
+        &mut self,
+        name: &str,
+        url: &str,
+        contents: &[u8],
+    ) -> Result<ImportedItem, PickError> {
+        let stored_name = self.unique_name(&normalize_name(name));
+        let path = self.dir.join(&stored_name);
+        write_atomically(&path, contents)?;
+        let item = ImportedItem {
+            name: stored_name,
+            path,
+            original_name: name.to_string(),
+            url: url.to_string(),
+            size: contents.len() as u64,
+            imported: SystemTime::now(),
+        };
+        self.items.push(item.clone());
+        if let Err(e) = self.save() {
+            self.items.pop();
+            fs::remove_file(&item.path).ok();
+            return Err(e);
+        }
+        Ok(item)
+    }
+
+    /// Remove the imported item named `name`, deleting its file.
+    pub fn remove(&mut self, name: &str) -> Result<Option<ImportedItem>, PickError> {
+        let Some(index) = self.items.iter().position(|item| item.name == name) else {
+            return Ok(None);
+        };
+        let item = self.items.remove(index);
+        match fs::remove_file(&item.path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => {
+                self.items.insert(index, item);
+                return Err(e.into());
+            }
+        }
+        self.save()?;
+        Ok(Some(item))
+    }
+
+    fn save(&self) -> Result<(), PickError> {
+        write_atomically(&self.dir.join(MANIFEST), &encode(&self.items))?;
+        Ok(())
+    }
+
+    // Names are compared case-insensitively, like the default file systems of
+    // Apple platforms do.
+    fn is_taken(&self, name: &str) -> bool {
+        let lower = name.to_lowercase();
+        lower == MANIFEST
+            || self
+                .items
+                .iter()
+                .any(|item| item.name.to_lowercase() == lower)
+            || fs::symlink_metadata(self.dir.join(name)).is_ok()
+    }
+
+    fn unique_name(&self, name: &str) -> String {
+        if !self.is_taken(name) {
+            return name.to_string();
+        }
+        let (stem, extension) = split_name(name);
+        let stem = strip_counter(stem);
+        (1..)
+            .map(|n| match extension {
+                Some(extension) => format!("{stem} ({n}).{extension}"),
+                None => format!("{stem} ({n})"),
+            })
+            .find(|name| !self.is_taken(name))
+            .unwrap()
+    }
+}
+
+/// Normalize to NFC, and replace the characters which couldn't be in a file
+/// name.
+fn normalize_name(name: &str) -> String {
+    let name = name
+        .nfc()
+        .map(|c| if c == '/' || c.is_control() { '_' } else { c })
+        .collect::<String>();
+    let name = name.trim();
+    if name.is_empty() || name == "." || name == ".." {
+        "Untitled".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/// Split into the stem and the extension. A leading dot doesn't start an
+/// extension.
+fn split_name(name: &str) -> (&str, Option<&str>) {
+    match name.rfind('.') {
+        Some(i) if i > 0 => (&name[..i], Some(&name[i + 1..])),
+        _ => (name, None),
+    }
+}
+
+/// Strip a ` (n)` suffix, so that importing `report (1).pdf` again makes
+/// `report (2).pdf`.
+fn strip_counter(stem: &str) -> &str {
+    let Some(rest) = stem.strip_suffix(')') else {
+        return stem;
+    };
+    match rest.rfind(" (") {
+        Some(i)
+            if !rest[i + 2..].is_empty() && rest[i + 2..].bytes().all(|b| b.is_ascii_digit()) =>
+        {
+            &stem[..i]
+        }
+        _ => stem,
+    }
+}
+
+fn encode(items: &[ImportedItem]) -> Vec<u8> {
+    let mut encoder = Encoder::new(MAGIC, VERSION);
+    encoder.u32(items.len() as u32);
+    for item in items {
+        encoder.bytes(item.name.as_bytes());
+        encoder.bytes(item.original_name.as_bytes());
+        encoder.bytes(item.url.as_bytes());
+        encoder.u64(item.size);
+        encoder.time(item.imported);
+    }
+    encoder.finish()
+}
+
+fn decode(dir: &Path, data: &[u8]) -> io::Result<Vec<ImportedItem>> {
+    let mut decoder = Decoder::new(data, MAGIC, VERSION, "import manifest")?;
+    let count = decoder.u32()?;
+    (0..count)
+        .map(|_| {
+            let name = decoder.string()?;
+            Ok(ImportedItem {
+                path: dir.join(&name),
+                name,
+                original_name: decoder.string()?,
+                url: decoder.string()?,
+                size: decoder.u64()?,
+                imported: decoder.time()?,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,169 @@
+use crate::{
+    backend::{PickerBackend, WriteOperation},
+    context::CallbackContext,
+    edit::write_file_with,
+    picker::{pick_file_closure, pick_files_stream, pick_with_access, PickAccess, PickFileSink},
+    reader::{local_path, read_range_with},
+    FileMetadata, FileReader, PickError, PickOptions, PickedFile, Presenter,
+};
+use futures_channel::oneshot;
+use futures_core::Stream;
+use futures_util::StreamExt;
+use std::{
+    fmt::Debug,
+    future::Future,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+pub(crate) fn pick_lazy_file_impl(
+    backend: Arc<dyn PickerBackend>,
+    presenter: &Presenter,
+    options: &PickOptions,
+) -> impl Future<Output = Result<LazyFile, PickError>> + Send + Sync {
+    let options = PickOptions {
+        streaming: true,
+        ..options.clone()
+    };
+    let pick = pick_with_access(backend, presenter, &options);
+    async move {
+        let (file, access) = pick.await?;
+        LazyFile::new(file, access)
+    }
+}
+
+pub(crate) fn pick_lazy_files_impl(
+    backend: Arc<dyn PickerBackend>,
+    presenter: &Presenter,
+    options: &PickOptions,
+) -> impl Stream<Item = Result<LazyFile, PickError>> + Send + Sync {
+    let options = PickOptions {
+        streaming: true,
+        ..options.clone()
+    };
+    let files = pick_files_stream(&*backend, presenter, &options, None);
+    let access = Arc::new(PickAccess::new(files.presentation(), backend));
+    files.map(move |res| res.and_then(|file| LazyFile::new(file, access.clone())))
+}
+
+/// A picked file which is read on demand.
+///
+/// It is created by [`FilePicker::pick_lazy_file`],
+/// [`FilePicker::pick_lazy_files`] or [`FilePicker::pick_file_for_editing`],
+/// without reading the content. The files of one pick and their clones share
+/// the access to the documents, which is released when the last of them
+/// drops.
+///
+/// [`FilePicker::pick_lazy_file`]: crate::FilePicker::pick_lazy_file
+/// [`FilePicker::pick_lazy_files`]: crate::FilePicker::pick_lazy_files
+/// [`FilePicker::pick_file_for_editing`]: crate::FilePicker::pick_file_for_editing
+#[derive(Clone)]
+pub struct LazyFile {
+    metadata: FileMetadata,
+    path: PathBuf,
+    access: Arc<PickAccess>,
+}
+
+impl Debug for LazyFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LazyFile")
+            .field("metadata", &self.metadata)
+            .finish_non_exhaustive()
+    }
+}
+
+impl LazyFile {
+    fn new(file: PickedFile, access: Arc<PickAccess>) -> Result<Self, PickError> {
+        let (metadata, _) = file.into_parts();
+        let path = local_path(&metadata)?;
+        Ok(Self {
+            metadata,
+            path,
+            access,
+        })
+    }
+
+    /// The metadata of the file when it was picked.
+    pub fn metadata(&self) -> &FileMetadata {
+        &self.metadata
+    }
+
+    /// The local path of the file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Read the whole file with coordination.
+    pub fn read(&self) -> impl Future<Output = Result<PickedFile, PickError>> + Send + Sync {
+        self.access.read_file(&self.path)
+    }
+
+    /// Read at most `len` bytes from `offset`. Fewer bytes are returned only
+    /// at the end of the file.
+    pub fn read_range(
+        &self,
+        offset: u64,
+        len: usize,
+    ) -> impl Future<Output = Result<Vec<u8>, PickError>> + Send + Sync {
+        read_range_with(&self.access, &self.path, offset, len)
+    }
+
+    /// Read the current metadata of the file with coordination, without its
+    /// content.
+    pub fn stat(&self) -> impl Future<Output = Result<FileMetadata, PickError>> + Send + Sync {
+        let (tx, rx) = oneshot::channel();
+        unsafe {
+            self.access.backend.stat(
+                &self.path,
+                pick_file_closure,
+                CallbackContext::into_raw(PickFileSink(Some(tx))),
+            );
+        }
+        let access = self.access.clone();
+        async move {
+            let res = rx.await.unwrap_or(Err(PickError::Cancelled));
+            drop(access);
+            res.map(|file| file.into_parts().0)
+        }
+    }
+
+    /// Overwrite the file in place with coordination, returning its new
+    /// metadata.
+    ///
+    /// A file picked as a copy is written to the copy only, and so the
+    /// documents to save back should be picked by
+    /// [`FilePicker::pick_file_for_editing`](crate::FilePicker::pick_file_for_editing).
+    pub fn write_all(
+        &self,
+        data: &[u8],
+    ) -> impl Future<Output = Result<FileMetadata, PickError>> + Send + Sync {
+        let operation = WriteOperation::Write {
+            path: &self.path,
+            data,
+        };
+        write_file_with(&self.access, &operation)
+    }
+
+    /// Replace the file atomically with coordination, returning its new
+    /// metadata. Unlike [`LazyFile::write_all`], a failed write never leaves
+    /// the file partially written.
+    pub fn replace(
+        &self,
+        data: &[u8],
+    ) -> impl Future<Output = Result<FileMetadata, PickError>> + Send + Sync {
+        let operation = WriteOperation::Replace {
+            path: &self.path,
+            data,
+        };
+        write_file_with(&self.access, &operation)
+    }
+
+    /// Read the file in chunks, sharing the access of this file.
+    pub fn reader(&self) -> FileReader {
+        FileReader::new(
+            self.metadata.clone(),
+            self.path.clone(),
+            self.access.clone(),
+        )
+    }
+}
@@ -0,0 +1,116 @@
+use objc::runtime::{objc_release, objc_retain, Object};
+use std::{
+    ffi::{c_int, c_void},
+    marker::PhantomData,
+};
+
+/// A proof that the code runs on the main thread, where UIKit must be used.
+///
+/// It is neither `Send` nor `Sync`, so that it couldn't leave the main thread,
+/// and the functions which take it could only be called there.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct MainThread {
+    _not_send: PhantomData<*mut ()>,
+}
+
+impl MainThread {
+    /// The token, if the current thread is the main thread.
+    pub fn new() -> Option<Self> {
+        is_main_thread().then_some(Self {
+            _not_send: PhantomData,
+        })
+    }
+
+    /// The token, without checking the current thread.
+    ///
+    /// # Safety
+    ///
+    /// It must be called on the main thread.
+    pub unsafe fn new_unchecked() -> Self {
+        Self {
+            _not_send: PhantomData,
+        }
+    }
+}
+
+#[repr(C)]
+struct DispatchQueue {
+    _private: [u8; 0],
+}
+
+extern "C" {
+    static _dispatch_main_q: DispatchQueue;
+
+    fn dispatch_async_f(
+        queue: *const DispatchQueue,
+        context: *mut c_void,
+        work: unsafe extern "C" fn(*mut c_void),
+    );
+
+    fn pthread_main_np() -> c_int;
+}
+
+type Work = Box<dyn FnOnce(MainThread) + Send>;
+
+unsafe extern "C" fn run(context: *mut c_void) {
+    let work = Box::from_raw(context as *mut Work);
+    work(MainThread::new_unchecked());
+}
+
+fn is_main_thread() -> bool {
+    unsafe { pthread_main_np() != 0 }
+}
+
+/// Run `f` later on the main queue, even if this is the main thread.
+fn dispatch_main(f: impl FnOnce(MainThread) + Send + 'static) {
+    let work: Work = Box::new(f);
+    unsafe { dispatch_async_f(&_dispatch_main_q, Box::into_raw(Box::new(work)).cast(), run) };
+}
+
+/// Run `f` on the main thread, at once if this is the main thread, or
+/// else later on the main queue.
+pub fn run_on_main(f: impl FnOnce(MainThread) + Send + 'static) {
+    match MainThread::new() {
+        Some(main) => f(main),
+        None => dispatch_main(f),
+    }
+}
+
+/// A retained Objective-C object, which is only used on the main thread
+/// and released there.
+pub struct MainObject(*mut Object);
+
+// SAFETY: the object is only messaged with a `MainThread`, and released
+// on the main queue. Retaining it is thread-safe.
+unsafe impl Send for MainObject {}
+unsafe impl Sync for MainObject {}
+
+impl MainObject {
+    /// Take the ownership of a retained object.
+    pub unsafe fn from_retained(ptr: *mut Object) -> Self {
+        Self(ptr)
+    }
+
+    pub unsafe fn retain(ptr: *mut Object) -> Self {
+        Self(objc_retain(ptr))
+    }
+
+    pub fn get(&self, _main: MainThread) -> *mut Object {
+        self.0
+    }
+}
+
+impl Clone for MainObject {
+    fn clone(&self) -> Self {
+        unsafe { Self::retain(self.0) }
+    }
+}
+
+impl Drop for MainObject {
+    fn drop(&mut self) {
+        // The release is deferred even on the main thread, as the object
+        // may be in the middle of a call.
+        let ptr = self.0 as usize;
+        dispatch_main(move |_| unsafe { objc_release(ptr as *mut Object) });
+    }
+}
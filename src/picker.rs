@@ -0,0 +1,809 @@
+use crate::{
+    backend::{
+        default_backend, PickMessageKind, PickRequest, PickerBackend, Presentation, RawCallback,
+        RequestId,
+    },
+    context::{CallbackContext, PickSink},
+    event::{event_closure, EventSender, EventSink},
+    lazy::{pick_lazy_file_impl, pick_lazy_files_impl},
+    reader::pick_file_reader_impl,
+    sniff::Sniffer,
+    timeout::{Deadline, Timeout},
+    ContentType, FileReader, ImportManager, ImportedItem, LazyFile, PickError, PickEvents,
+    PickedFile, Presenter, Signature, SniffPolicy,
+};
+use futures_channel::{mpsc, oneshot};
+use futures_core::Stream;
+use futures_util::StreamExt;
+use pin_project::pin_project;
+use std::{
+    ffi::c_void,
+    future::Future,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::Arc,
+    task::{ready, Context, Poll},
+    time::Duration,
+};
+
+/// How the picked documents are accessed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PickMode {
+    /// Open the original documents.
+    #[default]
+    Open,
+    /// Open copies of the documents, made in the temporary directory of the app.
+    Copy,
+}
+
+/// The modal presentation style of the picker.
+///
+/// The values are the same as `UIModalPresentationStyle`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[repr(isize)]
+pub enum PresentationStyle {
+    /// The default style chosen by the system.
+    #[default]
+    Automatic = -2,
+    /// Cover the screen.
+    FullScreen = 0,
+    /// Partially cover the underlying content.
+    PageSheet = 1,
+    /// Display the content centered in the screen.
+    FormSheet = 2,
+    /// Cover the presenting view controller.
+    CurrentContext = 3,
+    /// Cover the screen, keeping the presenting views visible underneath.
+    OverFullScreen = 5,
+    /// Cover the presenting view controller, keeping its views visible
+    /// underneath.
+    OverCurrentContext = 6,
+}
+
+/// The options of a picker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct PickOptions {
+    /// The allowed filename extensions.
+    pub extensions: Vec<String>,
+    /// The allowed content types, in addition to the extensions.
+    pub content_types: Vec<ContentType>,
+    /// Whether multiple files could be picked. It is ignored when picking one
+    /// file.
+    pub allow_multiple: bool,
+    /// The maximum count of files to pick. Files picked beyond it are ignored.
+    pub max_selection: Option<usize>,
+    /// The directory shown when the picker appears.
+    pub initial_directory: Option<PathBuf>,
+    /// Whether to show the filename extensions.
+    pub show_extensions: bool,
+    /// How the picked documents are accessed.
+    pub mode: PickMode,
+    /// The modal presentation style.
+    pub presentation_style: PresentationStyle,
+    /// Whether to pick folders instead of files.
+    pub folder: bool,
+    /// Whether to create a [`Bookmark`](crate::Bookmark) of every picked
+    /// file.
+    pub bookmarks: bool,
+    /// Whether to report the files without their content, to be read in
+    /// chunks by a [`FileReader`](crate::FileReader).
+    pub streaming: bool,
+    /// Whether to check the magic bytes of the picked files against the
+    /// extensions and content types. Files read in chunks aren't checked.
+    pub sniff: SniffPolicy,
+    /// Custom signatures for the content check, tried before the built-in
+    /// ones.
+    pub signatures: Vec<Signature>,
+    /// The maximum size of a file to read. Larger files fail with
+    /// [`PickError::FileTooLarge`] without being read.
+    pub max_file_size: Option<u64>,
+    /// The total size of the files to read. Files beyond it fail with
+    /// [`PickError::BudgetExceeded`] without being read.
+    pub byte_budget: Option<u64>,
+    /// The time to pick and read the files, after which the picker is
+    /// dismissed and the pick fails with [`PickError::TimedOut`].
+    pub timeout: Option<Duration>,
+    /// The maximum count of files read at once, off the main thread. It is
+    /// never zero.
+    pub concurrent_reads: usize,
+    /// Whether to report [`PickEvent`](crate::PickEvent)s. It is set by the
+    /// picks with events.
+    pub events: bool,
+}
+
+impl Default for PickOptions {
+    fn default() -> Self {
+        Self {
+            extensions: vec![],
+            content_types: vec![],
+            allow_multiple: true,
+            max_selection: None,
+            initial_directory: None,
+            show_extensions: true,
+            mode: PickMode::Open,
+            presentation_style: PresentationStyle::Automatic,
+            folder: false,
+            bookmarks: false,
+            streaming: false,
+            sniff: SniffPolicy::Off,
+            signatures: vec![],
+            max_file_size: None,
+            byte_budget: None,
+            timeout: None,
+            concurrent_reads: 1,
+            events: false,
+        }
+    }
+}
+
+/// A configurable document picker.
+///
+/// ```no_run
+/// # use file_picker_ios::FilePicker;
+/// # async fn f(presenter: &file_picker_ios::Presenter) {
+/// let file = FilePicker::new()
+///     .extensions(["pdf", "txt"])
+///     .show_extensions(false)
+///     .pick_file(presenter)
+///     .await;
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct FilePicker {
+    options: PickOptions,
+    backend: Option<Arc<dyn PickerBackend>>,
+}
+
+impl FilePicker {
+    /// Create a picker with the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The options of the picker.
+    pub fn options(&self) -> &PickOptions {
+        &self.options
+    }
+
+    /// Set the allowed filename extensions.
+    pub fn extensions<I>(mut self, extensions: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.options.extensions = extensions.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Set the allowed content types, in addition to the extensions.
+    ///
+    /// ```no_run
+    /// # use file_picker_ios::{ContentType, FilePicker};
+    /// # fn f() -> Result<(), file_picker_ios::PickError> {
+    /// let picker = FilePicker::new().content_types([
+    ///     ContentType::IMAGE,
+    ///     ContentType::from_mime_type("application/pdf")?,
+    /// ]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn content_types(mut self, types: impl IntoIterator<Item = ContentType>) -> Self {
+        self.options.content_types = types.into_iter().collect();
+        self
+    }
+
+    /// Set whether [`FilePicker::pick_files`] allows multiple selection.
+    /// It is `true` by default.
+    pub fn multiple(mut self, multiple: bool) -> Self {
+        self.options.allow_multiple = multiple;
+        self
+    }
+
+    /// Set the maximum count of files to pick.
+    pub fn max_selection(mut self, max: usize) -> Self {
+        self.options.max_selection = Some(max);
+        self
+    }
+
+    /// Set the directory shown when the picker appears.
+    pub fn initial_directory(mut self, path: impl Into<PathBuf>) -> Self {
+        self.options.initial_directory = Some(path.into());
+        self
+    }
+
+    /// Set whether to show the filename extensions. It is `true` by default.
+    pub fn show_extensions(mut self, show: bool) -> Self {
+        self.options.show_extensions = show;
+        self
+    }
+
+    /// Set how the picked documents are accessed.
+    pub fn mode(mut self, mode: PickMode) -> Self {
+        self.options.mode = mode;
+        self
+    }
+
+    /// Set the modal presentation style.
+    pub fn presentation_style(mut self, style: PresentationStyle) -> Self {
+        self.options.presentation_style = style;
+        self
+    }
+
+    /// Set whether to create a [`Bookmark`](crate::Bookmark) of every picked
+    /// file. It is `false` by default.
+    pub fn bookmarks(mut self, bookmarks: bool) -> Self {
+        self.options.bookmarks = bookmarks;
+        self
+    }
+
+    /// Set whether to check the magic bytes of the picked files against the
+    /// extensions and content types. It is [`SniffPolicy::Off`] by default.
+    ///
+    /// ```no_run
+    /// # use file_picker_ios::{ContentType, FilePicker, Signature, SniffPolicy};
+    /// # fn f() -> Result<(), file_picker_ios::PickError> {
+    /// let sketch = ContentType::from_identifier("com.example.sketch")?;
+    /// let picker = FilePicker::new()
+    ///     .content_types([ContentType::PDF, sketch.clone()])
+    ///     .sniff(SniffPolicy::Reject)
+    ///     .signature(Signature::new(sketch, *b"SKCH"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn sniff(mut self, policy: SniffPolicy) -> Self {
+        self.options.sniff = policy;
+        self
+    }
+
+    /// Add a custom signature for the content check.
+    pub fn signature(mut self, signature: Signature) -> Self {
+        self.options.signatures.push(signature);
+        self
+    }
+
+    /// Set the maximum size of a file to read.
+    ///
+    /// The size is checked from the metadata before reading, and a larger
+    /// file yields [`PickError::FileTooLarge`], without stopping the other
+    /// files. Files of unknown size, and folders or files read in chunks,
+    /// aren't limited.
+    pub fn max_file_size(mut self, bytes: u64) -> Self {
+        self.options.max_file_size = Some(bytes);
+        self
+    }
+
+    /// Set the total size of the files to read.
+    ///
+    /// The files are counted in the order of selection, and a file which
+    /// doesn't fit in what remains yields [`PickError::BudgetExceeded`]
+    /// without being read, while the smaller files after it may still fit.
+    /// Files read at once are counted in the order they are checked instead.
+    /// Like [`FilePicker::max_file_size`], only files of known size are
+    /// counted.
+    pub fn byte_budget(mut self, bytes: u64) -> Self {
+        self.options.byte_budget = Some(bytes);
+        self
+    }
+
+    /// Set the time to pick and read the files.
+    ///
+    /// When it elapses, the picker is dismissed, the files which aren't read
+    /// yet are dropped, and the pick fails with [`PickError::TimedOut`]. A
+    /// stream yields it as the last item.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.options.timeout = Some(timeout);
+        self
+    }
+
+    /// Set the maximum count of files read at once. It is 1 by default.
+    ///
+    /// The files are read in parallel on a background queue, at most
+    /// `count - 1` ahead of the ones taken from the stream, and are still
+    /// yielded in the order of selection.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `count` is zero.
+    pub fn concurrent_reads(mut self, count: usize) -> Self {
+        assert!(count > 0, "the count of concurrent reads must not be zero");
+        self.options.concurrent_reads = count;
+        self
+    }
+
+    /// Set the backend presenting the picker. It is [`default_backend`] by
+    /// default.
+    pub fn backend(mut self, backend: Arc<dyn PickerBackend>) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+
+    fn with_backend<T>(&self, f: impl FnOnce(&dyn PickerBackend) -> T) -> T {
+        match &self.backend {
+            Some(backend) => f(&**backend),
+            None => f(&*default_backend()),
+        }
+    }
+
+    /// Pick one file.
+    pub fn pick_file(
+        &self,
+        presenter: &Presenter,
+    ) -> impl Future<Output = Result<PickedFile, PickError>> + Send + Sync {
+        self.with_backend(|backend| pick_file_impl(backend, presenter, &self.options, None))
+    }
+
+    /// Pick one file, reporting the events of the pick next to the result.
+    ///
+    /// ```no_run
+    /// # use file_picker_ios::{FilePicker, PickEvent};
+    /// # use futures_util::{future::join, StreamExt};
+    /// # async fn f(presenter: &file_picker_ios::Presenter) {
+    /// let (file, events) = FilePicker::new().pick_file_with_events(presenter);
+    /// let progress = events.for_each(|event| async move {
+    ///     if let PickEvent::Progress { completed, total, .. } = event {
+    ///         println!("{completed} of {total} bytes");
+    ///     }
+    /// });
+    /// let (file, _) = join(file, progress).await;
+    /// # }
+    /// ```
+    pub fn pick_file_with_events(
+        &self,
+        presenter: &Presenter,
+    ) -> (
+        impl Future<Output = Result<PickedFile, PickError>> + Send + Sync,
+        PickEvents,
+    ) {
+        let (tx, events) = PickEvents::channel();
+        let file = self
+            .with_backend(|backend| pick_file_impl(backend, presenter, &self.options, Some(tx)));
+        (file, events)
+    }
+
+    /// Pick multiple files as copies, and import them into `manager`.
+    ///
+    /// The imported items are yielded like the files of
+    /// [`pick_files`](crate::pick_files), and a file which couldn't be read or
+    /// written yields an error.
+    pub fn import_files<'a>(
+        &self,
+        presenter: &Presenter,
+        manager: &'a mut ImportManager,
+    ) -> impl Stream<Item = Result<ImportedItem, PickError>> + Send + Sync + 'a {
+        let options = PickOptions {
+            mode: PickMode::Copy,
+            ..self.options.clone()
+        };
+        let files =
+            self.with_backend(|backend| pick_files_stream(backend, presenter, &options, None));
+        files.map(move |res| res.and_then(|file| manager.import(&file)))
+    }
+
+    /// Pick one file to be read in chunks, instead of loading it at once.
+    pub fn pick_file_reader(
+        &self,
+        presenter: &Presenter,
+    ) -> impl Future<Output = Result<FileReader, PickError>> + Send + Sync {
+        let backend = self.backend.clone().unwrap_or_else(default_backend);
+        pick_file_reader_impl(backend, presenter, &self.options)
+    }
+
+    /// Pick one file to be read on demand, instead of loading it at once.
+    pub fn pick_lazy_file(
+        &self,
+        presenter: &Presenter,
+    ) -> impl Future<Output = Result<LazyFile, PickError>> + Send + Sync {
+        let backend = self.backend.clone().unwrap_or_else(default_backend);
+        pick_lazy_file_impl(backend, presenter, &self.options)
+    }
+
+    /// Pick one document to be read and saved back in place.
+    ///
+    /// The original document is opened even if [`PickMode::Copy`] is set, and
+    /// is written with [`LazyFile::write_all`] or [`LazyFile::replace`].
+    ///
+    /// ```no_run
+    /// # use file_picker_ios::FilePicker;
+    /// # async fn f(presenter: &file_picker_ios::Presenter) -> Result<(), file_picker_ios::PickError> {
+    /// let file = FilePicker::new().extensions(["txt"]).pick_file_for_editing(presenter).await?;
+    /// let mut text = file.read().await?.to_vec();
+    /// text.extend_from_slice(b"\nedited");
+    /// file.replace(&text).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn pick_file_for_editing(
+        &self,
+        presenter: &Presenter,
+    ) -> impl Future<Output = Result<LazyFile, PickError>> + Send + Sync {
+        let options = PickOptions {
+            mode: PickMode::Open,
+            ..self.options.clone()
+        };
+        let backend = self.backend.clone().unwrap_or_else(default_backend);
+        pick_lazy_file_impl(backend, presenter, &options)
+    }
+
+    /// Pick multiple files to be read on demand.
+    ///
+    /// The files are yielded without their content. The picker is dismissed
+    /// when the stream drops, while the files keep the access to the
+    /// documents.
+    ///
+    /// ```no_run
+    /// # use file_picker_ios::FilePicker;
+    /// # use futures_util::TryStreamExt;
+    /// # async fn f(presenter: &file_picker_ios::Presenter) -> Result<(), file_picker_ios::PickError> {
+    /// let files: Vec<_> = FilePicker::new().pick_lazy_files(presenter).try_collect().await?;
+    /// for file in &files {
+    ///     let header = file.read_range(0, 16).await?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn pick_lazy_files(
+        &self,
+        presenter: &Presenter,
+    ) -> impl Stream<Item = Result<LazyFile, PickError>> + Send + Sync {
+        let backend = self.backend.clone().unwrap_or_else(default_backend);
+        pick_lazy_files_impl(backend, presenter, &self.options)
+    }
+
+    /// Pick multiple files.
+    ///
+    /// See [`pick_files`](crate::pick_files).
+    pub fn pick_files(
+        &self,
+        presenter: &Presenter,
+    ) -> impl Stream<Item = Result<PickedFile, PickError>> + Send + Sync {
+        self.with_backend(|backend| pick_files_stream(backend, presenter, &self.options, None))
+    }
+
+    /// Pick multiple files, reporting the events of the pick next to the
+    /// files.
+    pub fn pick_files_with_events(
+        &self,
+        presenter: &Presenter,
+    ) -> (
+        impl Stream<Item = Result<PickedFile, PickError>> + Send + Sync,
+        PickEvents,
+    ) {
+        let (tx, events) = PickEvents::channel();
+        let files = self
+            .with_backend(|backend| pick_files_stream(backend, presenter, &self.options, Some(tx)));
+        (files, events)
+    }
+}
+
+pub(crate) struct PickFileSink(pub Option<oneshot::Sender<Result<PickedFile, PickError>>>);
+
+impl PickFileSink {
+    fn send(&mut self, res: Result<PickedFile, PickError>) {
+        if let Some(sender) = self.0.take() {
+            sender.send(res).ok();
+        }
+    }
+}
+
+impl PickSink for PickFileSink {
+    fn file(&mut self, file: PickedFile) {
+        self.send(Ok(file));
+    }
+
+    fn file_error(&mut self, error: PickError) {
+        self.send(Err(error));
+    }
+
+    fn failed(&mut self, error: PickError) {
+        self.send(Err(error));
+    }
+
+    fn cancelled(&mut self) {
+        self.send(Err(PickError::Cancelled));
+    }
+
+    fn finished(mut self) {
+        self.send(Err(PickError::Cancelled));
+    }
+}
+
+pub(crate) unsafe extern "C" fn pick_file_closure(
+    kind: PickMessageKind,
+    data: *const c_void,
+    len: usize,
+    closure_data: *mut c_void,
+) {
+    CallbackContext::<PickFileSink>::dispatch(closure_data, kind, data, len);
+}
+
+fn with_request(file: PickedFile, id: RequestId) -> PickedFile {
+    let (mut metadata, handle) = file.into_parts();
+    metadata.request = Some(id);
+    PickedFile::new(metadata, handle)
+}
+
+/// Present a picker reporting to `sink` through `closure`, or also to `events`
+/// if any.
+unsafe fn present_with_events<S: PickSink>(
+    backend: &dyn PickerBackend,
+    request: &PickRequest<'_>,
+    closure: RawCallback,
+    sink: S,
+    events: Option<EventSender>,
+) -> Presentation {
+    match events {
+        Some(events) => backend.present(
+            request,
+            event_closure::<S>,
+            CallbackContext::into_raw(EventSink { sink, events }),
+        ),
+        None => backend.present(request, closure, CallbackContext::into_raw(sink)),
+    }
+}
+
+pub(crate) fn pick_file_impl(
+    backend: &dyn PickerBackend,
+    presenter: &Presenter,
+    options: &PickOptions,
+    events: Option<EventSender>,
+) -> impl Future<Output = Result<PickedFile, PickError>> + Send + Sync {
+    let options = PickOptions {
+        allow_multiple: false,
+        events: events.is_some(),
+        ..options.clone()
+    };
+    let id = RequestId::next();
+    let (tx, rx) = oneshot::channel();
+    let delegate = unsafe {
+        present_with_events(
+            backend,
+            &PickRequest {
+                id,
+                presenter,
+                options: &options,
+            },
+            pick_file_closure,
+            PickFileSink(Some(tx)),
+            events,
+        )
+    };
+    delegate.request_next();
+    let sniffer = Sniffer::new(&options);
+    let f = async move {
+        rx.await
+            .unwrap_or(Err(PickError::Cancelled))
+            .and_then(|file| sniffer.check(with_request(file, id)))
+    };
+    PickFileFuture {
+        f: Timeout::new(f, options.timeout),
+        delegate,
+    }
+}
+
+/// Dismisses the picker when it drops, or when the pick times out.
+#[pin_project]
+struct PickFileFuture<F: Future<Output = Result<PickedFile, PickError>> + Send + Sync> {
+    #[pin]
+    f: Timeout<F>,
+    delegate: Presentation,
+}
+
+impl<F: Future<Output = Result<PickedFile, PickError>> + Send + Sync> Future for PickFileFuture<F> {
+    type Output = Result<PickedFile, PickError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let res = ready!(this.f.poll(cx));
+        if matches!(res, Err(PickError::TimedOut)) {
+            this.delegate.dismiss();
+        }
+        Poll::Ready(res)
+    }
+}
+
+/// Keeps a presentation, and so the access to the documents picked in it,
+/// alive.
+pub(crate) struct PickAccess {
+    _presentation: Arc<Presentation>,
+    pub backend: Arc<dyn PickerBackend>,
+}
+
+impl PickAccess {
+    pub fn new(presentation: Arc<Presentation>, backend: Arc<dyn PickerBackend>) -> Self {
+        Self {
+            _presentation: presentation,
+            backend,
+        }
+    }
+
+    /// Read the file at `path` with coordination.
+    pub fn read_file(
+        self: &Arc<Self>,
+        path: &Path,
+    ) -> impl Future<Output = Result<PickedFile, PickError>> + Send + Sync {
+        let (tx, rx) = oneshot::channel();
+        unsafe {
+            self.backend.read_file(
+                path,
+                pick_file_closure,
+                CallbackContext::into_raw(PickFileSink(Some(tx))),
+            );
+        }
+        let access = self.clone();
+        async move {
+            let res = rx.await.unwrap_or(Err(PickError::Cancelled));
+            drop(access);
+            res
+        }
+    }
+}
+
+/// Pick one document which is reported without content, keeping the access to
+/// it.
+pub(crate) fn pick_with_access(
+    backend: Arc<dyn PickerBackend>,
+    presenter: &Presenter,
+    options: &PickOptions,
+) -> impl Future<Output = Result<(PickedFile, Arc<PickAccess>), PickError>> + Send + Sync {
+    let options = PickOptions {
+        allow_multiple: false,
+        ..options.clone()
+    };
+    let id = RequestId::next();
+    let (tx, rx) = oneshot::channel();
+    let presentation = unsafe {
+        backend.present(
+            &PickRequest {
+                id,
+                presenter,
+                options: &options,
+            },
+            pick_file_closure,
+            CallbackContext::into_raw(PickFileSink(Some(tx))),
+        )
+    };
+    presentation.request_next();
+    let access = PickAccess::new(Arc::new(presentation), backend);
+    let file = Timeout::new(
+        async move {
+            rx.await
+                .unwrap_or(Err(PickError::Cancelled))
+                .map(|file| with_request(file, id))
+        },
+        options.timeout,
+    );
+    async move {
+        // A timeout drops the access, and so dismisses the picker.
+        let file = file.await?;
+        Ok((file, Arc::new(access)))
+    }
+}
+
+struct PickFilesSink(mpsc::UnboundedSender<Result<PickedFile, PickError>>);
+
+impl PickSink for PickFilesSink {
+    fn file(&mut self, file: PickedFile) {
+        self.0.unbounded_send(Ok(file)).ok();
+    }
+
+    fn file_error(&mut self, error: PickError) {
+        self.0.unbounded_send(Err(error)).ok();
+    }
+
+    fn failed(&mut self, error: PickError) {
+        self.0.unbounded_send(Err(error)).ok();
+    }
+
+    fn cancelled(&mut self) {
+        self.0.unbounded_send(Err(PickError::Cancelled)).ok();
+    }
+
+    fn finished(self) {}
+}
+
+unsafe extern "C" fn pick_files_closure(
+    kind: PickMessageKind,
+    data: *const c_void,
+    len: usize,
+    closure_data: *mut c_void,
+) {
+    CallbackContext::<PickFilesSink>::dispatch(closure_data, kind, data, len);
+}
+
+pub(crate) fn pick_files_stream(
+    backend: &dyn PickerBackend,
+    presenter: &Presenter,
+    options: &PickOptions,
+    events: Option<EventSender>,
+) -> PickFilesStream {
+    let options = PickOptions {
+        events: events.is_some(),
+        ..options.clone()
+    };
+    let id = RequestId::next();
+    let (tx, rx) = mpsc::unbounded();
+    let delegate = unsafe {
+        present_with_events(
+            backend,
+            &PickRequest {
+                id,
+                presenter,
+                options: &options,
+            },
+            pick_files_closure,
+            PickFilesSink(tx),
+            events,
+        )
+    };
+    PickFilesStream {
+        id,
+        rx,
+        delegate: Arc::new(delegate),
+        requested: false,
+        sniffer: Sniffer::new(&options),
+        deadline: options.timeout.map(Deadline::after),
+        timed_out: false,
+    }
+}
+
+/// Dismisses the picker when it drops, even if the presentation is kept by the
+/// files picked in it.
+pub(crate) struct PickFilesStream {
+    id: RequestId,
+    rx: mpsc::UnboundedReceiver<Result<PickedFile, PickError>>,
+    delegate: Arc<Presentation>,
+    requested: bool,
+    sniffer: Sniffer,
+    deadline: Option<Deadline>,
+    timed_out: bool,
+}
+
+impl PickFilesStream {
+    /// The presentation, to keep the access to the picked files.
+    pub fn presentation(&self) -> Arc<Presentation> {
+        self.delegate.clone()
+    }
+}
+
+impl Drop for PickFilesStream {
+    fn drop(&mut self) {
+        self.delegate.dismiss();
+    }
+}
+
+impl Stream for PickFilesStream {
+    type Item = Result<PickedFile, PickError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.timed_out {
+            return Poll::Ready(None);
+        }
+        loop {
+            match self.rx.poll_next_unpin(cx) {
+                Poll::Ready(Some(res)) => {
+                    self.requested = false;
+                    let id = self.id;
+                    let res = res.and_then(|file| self.sniffer.check(with_request(file, id)));
+                    return Poll::Ready(Some(res));
+                }
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending if self.requested => break,
+                Poll::Pending => {
+                    self.requested = true;
+                    self.delegate.request_next();
+                }
+            }
+        }
+        let elapsed = self
+            .deadline
+            .as_mut()
+            .is_some_and(|deadline| Pin::new(deadline).poll(cx).is_ready());
+        if elapsed {
+            self.timed_out = true;
+            self.delegate.dismiss();
+            return Poll::Ready(Some(Err(PickError::TimedOut)));
+        }
+        Poll::Pending
+    }
+}
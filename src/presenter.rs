@@ -0,0 +1,110 @@
+use crate::{Object, PickError};
+use raw_window_handle::{HasWindowHandle, RawWindowHandle, UiKitWindowHandle};
+
+#[cfg(target_os = "ios")]
+use crate::main_thread::{MainObject, MainThread};
+
+/// Where the pickers are presented.
+///
+/// It is resolved when a picker is presented, to the top-most view controller
+/// presented over the given one, so that a picker isn't ignored because
+/// another modal is already up. If there's no view controller at all, the
+/// pick fails with [`PickError::NoPresenter`].
+///
+/// ```no_run
+/// # use file_picker_ios::{FilePicker, Presenter};
+/// # async fn f(window: &impl raw_window_handle::HasWindowHandle) -> Result<(), file_picker_ios::PickError> {
+/// let presenter = Presenter::from_window_handle(window)?;
+/// let file = FilePicker::new().pick_file(&presenter).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct Presenter {
+    // A view controller or a view, or `None` for the key window.
+    #[cfg(target_os = "ios")]
+    object: Option<MainObject>,
+}
+
+impl Presenter {
+    /// Present over the root view controller of the key window of the active
+    /// scene.
+    pub fn key_window() -> Self {
+        Self::default()
+    }
+
+    /// Present over the view controller.
+    ///
+    /// # Safety
+    ///
+    /// `controller` must be null or a valid `UIViewController`. It is
+    /// retained. A null one is the same as [`Presenter::key_window`].
+    pub unsafe fn from_view_controller(controller: *mut Object) -> Self {
+        Self::from_object(controller)
+    }
+
+    /// Present over the view controller owning the view, or the root view
+    /// controller of its window.
+    ///
+    /// # Safety
+    ///
+    /// `view` must be null or a valid `UIView`. It is retained. A null one is
+    /// the same as [`Presenter::key_window`].
+    pub unsafe fn from_view(view: *mut Object) -> Self {
+        Self::from_object(view)
+    }
+
+    /// Present over the view controller of the handle, or else of its view.
+    ///
+    /// # Safety
+    ///
+    /// The pointers of `handle` must be valid.
+    pub unsafe fn from_raw_window_handle(handle: &UiKitWindowHandle) -> Self {
+        match handle.ui_view_controller {
+            Some(controller) => Self::from_view_controller(controller.as_ptr().cast()),
+            None => Self::from_view(handle.ui_view.as_ptr().cast()),
+        }
+    }
+
+    /// Present over the window of a windowing library, like winit.
+    ///
+    /// A window which isn't a UIKit one fails with
+    /// [`PickError::UnsupportedPlatform`].
+    pub fn from_window_handle(window: &impl HasWindowHandle) -> Result<Self, PickError> {
+        let handle = window
+            .window_handle()
+            .map_err(|_| PickError::UnsupportedPlatform)?;
+        match handle.as_raw() {
+            // SAFETY: the pointers are valid while the handle is borrowed, and
+            // the object is retained before it ends.
+            RawWindowHandle::UiKit(handle) => Ok(unsafe { Self::from_raw_window_handle(&handle) }),
+            _ => Err(PickError::UnsupportedPlatform),
+        }
+    }
+
+    #[cfg(target_os = "ios")]
+    unsafe fn from_object(object: *mut Object) -> Self {
+        Self {
+            object: (!object.is_null()).then(|| MainObject::retain(object)),
+        }
+    }
+
+    #[cfg(not(target_os = "ios"))]
+    unsafe fn from_object(_object: *mut Object) -> Self {
+        Self::default()
+    }
+
+    /// The view controller, view, or null for the key window.
+    #[cfg(target_os = "ios")]
+    pub(crate) fn get(&self, main: MainThread) -> *mut Object {
+        self.object
+            .as_ref()
+            .map_or(std::ptr::null_mut(), |object| object.get(main))
+    }
+}
+
+impl std::fmt::Debug for Presenter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Presenter").finish_non_exhaustive()
+    }
+}
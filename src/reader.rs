@@ -0,0 +1,337 @@
+use crate::{
+    backend::{PickMessageKind, PickerBackend},
+    context::{CallbackContext, PickSink},
+    picker::{pick_with_access, PickAccess},
+    FileMetadata, PickError, PickOptions, PickedFile, Presenter,
+};
+use futures_channel::oneshot;
+use futures_core::Stream;
+use futures_io::{AsyncRead, AsyncSeek};
+use std::{
+    ffi::c_void,
+    fmt::Debug,
+    future::Future,
+    io::{self, SeekFrom},
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::Arc,
+    task::{ready, Context, Poll},
+};
+
+const DEFAULT_CHUNK_SIZE: usize = 1 << 20;
+
+pub(crate) fn pick_file_reader_impl(
+    backend: Arc<dyn PickerBackend>,
+    presenter: &Presenter,
+    options: &PickOptions,
+) -> impl Future<Output = Result<FileReader, PickError>> + Send + Sync {
+    let options = PickOptions {
+        streaming: true,
+        ..options.clone()
+    };
+    let pick = pick_with_access(backend, presenter, &options);
+    async move {
+        let (file, access) = pick.await?;
+        let (metadata, _) = file.into_parts();
+        let path = local_path(&metadata)?;
+        Ok(FileReader::new(metadata, path, access))
+    }
+}
+
+pub(crate) fn local_path(metadata: &FileMetadata) -> Result<PathBuf, PickError> {
+    let path = metadata
+        .path
+        .clone()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "the file has no local path"))?;
+    Ok(path)
+}
+
+type ChunkSender = oneshot::Sender<Result<Vec<u8>, PickError>>;
+
+struct ChunkSink(Option<ChunkSender>);
+
+impl ChunkSink {
+    fn send(&mut self, res: Result<Vec<u8>, PickError>) {
+        if let Some(sender) = self.0.take() {
+            sender.send(res).ok();
+        }
+    }
+}
+
+impl PickSink for ChunkSink {
+    fn file(&mut self, _file: PickedFile) {}
+
+    fn file_error(&mut self, error: PickError) {
+        self.send(Err(error));
+    }
+
+    fn failed(&mut self, error: PickError) {
+        self.send(Err(error));
+    }
+
+    fn cancelled(&mut self) {
+        self.send(Err(PickError::Cancelled));
+    }
+
+    fn chunk(&mut self, data: &[u8]) {
+        self.send(Ok(data.to_vec()));
+    }
+
+    fn finished(mut self) {
+        self.send(Err(PickError::Cancelled));
+    }
+}
+
+unsafe extern "C" fn chunk_closure(
+    kind: PickMessageKind,
+    data: *const c_void,
+    len: usize,
+    closure_data: *mut c_void,
+) {
+    CallbackContext::<ChunkSink>::dispatch(closure_data, kind, data, len);
+}
+
+/// Read a range of the file at `path`, keeping the access until it is read.
+pub(crate) fn read_range_with(
+    access: &Arc<PickAccess>,
+    path: &Path,
+    offset: u64,
+    len: usize,
+) -> impl Future<Output = Result<Vec<u8>, PickError>> + Send + Sync {
+    let rx = read_range(access, path, offset, len);
+    let access = access.clone();
+    async move {
+        let res = rx.await.unwrap_or(Err(PickError::Cancelled));
+        drop(access);
+        res
+    }
+}
+
+fn read_range(
+    access: &PickAccess,
+    path: &Path,
+    offset: u64,
+    len: usize,
+) -> oneshot::Receiver<Result<Vec<u8>, PickError>> {
+    let (tx, rx) = oneshot::channel();
+    unsafe {
+        access.backend.read_range(
+            path,
+            offset,
+            len,
+            chunk_closure,
+            CallbackContext::into_raw(ChunkSink(Some(tx))),
+        );
+    }
+    rx
+}
+
+/// A picked file which is read in chunks on demand, keeping the memory
+/// bounded.
+///
+/// It is created by [`FilePicker::pick_file_reader`], and keeps the access to
+/// the file until it drops. Every chunk is a coordinated read of the native
+/// side. It could be consumed as [`AsyncRead`] and [`AsyncSeek`], or as a
+/// [`Stream`] of the chunks from the current position, which ends after the
+/// first error. With the `tokio` feature, it implements the I/O traits of
+/// tokio too.
+///
+/// [`FilePicker::pick_file_reader`]: crate::FilePicker::pick_file_reader
+pub struct FileReader {
+    metadata: FileMetadata,
+    path: PathBuf,
+    access: Arc<PickAccess>,
+    chunk_size: usize,
+    // The position of the next byte to yield. The unyielded bytes of the
+    // buffer start at it, and the pending read starts at it too.
+    pos: u64,
+    buffer: Vec<u8>,
+    buffer_pos: usize,
+    pending: Option<oneshot::Receiver<Result<Vec<u8>, PickError>>>,
+    // Set once the stream yields an error, ending it until the next seek.
+    failed: bool,
+}
+
+impl Debug for FileReader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FileReader")
+            .field("metadata", &self.metadata)
+            .field("chunk_size", &self.chunk_size)
+            .field("pos", &self.pos)
+            .finish_non_exhaustive()
+    }
+}
+
+impl FileReader {
+    pub(crate) fn new(metadata: FileMetadata, path: PathBuf, access: Arc<PickAccess>) -> Self {
+        Self {
+            metadata,
+            path,
+            access,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            pos: 0,
+            buffer: vec![],
+            buffer_pos: 0,
+            pending: None,
+            failed: false,
+        }
+    }
+
+    /// The metadata of the file.
+    pub fn metadata(&self) -> &FileMetadata {
+        &self.metadata
+    }
+
+    /// The local path of the file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The position of the next byte to read.
+    pub fn position(&self) -> u64 {
+        self.pos
+    }
+
+    /// Set the maximum size of a chunk. It is 1 MiB by default.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero.
+    pub fn chunk_size(mut self, size: usize) -> Self {
+        assert!(size > 0, "the chunk size must not be zero");
+        self.chunk_size = size;
+        self
+    }
+
+    /// Read at most `len` bytes from `offset`, regardless of the current
+    /// position. Fewer bytes are returned only at the end of the file.
+    pub fn read_range(
+        &self,
+        offset: u64,
+        len: usize,
+    ) -> impl Future<Output = Result<Vec<u8>, PickError>> + Send + Sync {
+        read_range_with(&self.access, &self.path, offset, len)
+    }
+
+    /// Make sure there are unyielded bytes in the buffer, unless the file
+    /// ends.
+    fn poll_fill(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), PickError>> {
+        if self.buffer_pos < self.buffer.len() {
+            return Poll::Ready(Ok(()));
+        }
+        let rx = self
+            .pending
+            .get_or_insert_with(|| read_range(&self.access, &self.path, self.pos, self.chunk_size));
+        let res = ready!(Pin::new(rx).poll(cx)).unwrap_or(Err(PickError::Cancelled));
+        self.pending = None;
+        self.buffer = res?;
+        self.buffer_pos = 0;
+        Poll::Ready(Ok(()))
+    }
+
+    fn consume(&mut self, len: usize) {
+        self.buffer_pos += len;
+        self.pos += len as u64;
+    }
+}
+
+impl FileReader {
+    fn seek_to(&mut self, position: SeekFrom) -> io::Result<u64> {
+        let pos = match position {
+            SeekFrom::Start(pos) => Some(pos),
+            SeekFrom::Current(delta) => self.pos.checked_add_signed(delta),
+            SeekFrom::End(delta) => {
+                let size = self.metadata.size.ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::Unsupported, "the file size is unknown")
+                })?;
+                size.checked_add_signed(delta)
+            }
+        };
+        let pos = pos
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid seek position"))?;
+        if pos != self.pos {
+            self.pos = pos;
+            self.buffer.clear();
+            self.buffer_pos = 0;
+            self.pending = None;
+        }
+        self.failed = false;
+        Ok(pos)
+    }
+
+    fn poll_read_into(&mut self, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        ready!(self.poll_fill(cx))?;
+        let available = &self.buffer[self.buffer_pos..];
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.consume(len);
+        Poll::Ready(Ok(len))
+    }
+}
+
+impl AsyncRead for FileReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.get_mut().poll_read_into(cx, buf)
+    }
+}
+
+impl AsyncSeek for FileReader {
+    fn poll_seek(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        position: SeekFrom,
+    ) -> Poll<io::Result<u64>> {
+        Poll::Ready(self.get_mut().seek_to(position))
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl tokio::io::AsyncRead for FileReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let len = ready!(self.get_mut().poll_read_into(cx, buf.initialize_unfilled()))?;
+        buf.advance(len);
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl tokio::io::AsyncSeek for FileReader {
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        self.get_mut().seek_to(position).map(drop)
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        Poll::Ready(Ok(self.pos))
+    }
+}
+
+impl Stream for FileReader {
+    type Item = Result<Vec<u8>, PickError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.failed {
+            return Poll::Ready(None);
+        }
+        if let Err(e) = ready!(this.poll_fill(cx)) {
+            this.failed = true;
+            return Poll::Ready(Some(Err(e)));
+        }
+        if this.buffer_pos == this.buffer.len() {
+            return Poll::Ready(None);
+        }
+        let mut chunk = std::mem::take(&mut this.buffer);
+        chunk.drain(..this.buffer_pos);
+        this.buffer_pos = 0;
+        this.pos += chunk.len() as u64;
+        Poll::Ready(Some(Ok(chunk)))
+    }
+}
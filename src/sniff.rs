@@ -0,0 +1,200 @@
+use crate::{ContentType, PickError, PickOptions, PickedFile};
+use std::{borrow::Cow, path::Path};
+
+/// What to do with picked files whose content doesn't match the requested
+/// types.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SniffPolicy {
+    /// Don't inspect the content.
+    #[default]
+    Off,
+    /// Deliver the files, with the result in [`FileMetadata::sniffed`].
+    ///
+    /// [`FileMetadata::sniffed`]: crate::FileMetadata::sniffed
+    Flag,
+    /// Fail the files which don't match with [`PickError::TypeMismatch`].
+    Reject,
+}
+
+/// The magic bytes identifying a content type.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Signature {
+    content_type: ContentType,
+    offset: usize,
+    magic: Cow<'static, [u8]>,
+}
+
+impl Signature {
+    /// The content starting with `magic` is of `content_type`.
+    pub fn new(content_type: ContentType, magic: impl Into<Vec<u8>>) -> Self {
+        Self {
+            content_type,
+            offset: 0,
+            magic: Cow::Owned(magic.into()),
+        }
+    }
+
+    /// Look for the magic at `offset` instead of the start.
+    pub fn at(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// The content type it identifies.
+    pub fn content_type(&self) -> &ContentType {
+        &self.content_type
+    }
+
+    /// Whether `data` has the magic.
+    pub fn matches(&self, data: &[u8]) -> bool {
+        data.get(self.offset..)
+            .is_some_and(|data| data.starts_with(&self.magic))
+    }
+}
+
+macro_rules! signatures {
+    ($($id:literal $(@ $offset:literal)?: $magic:literal,)*) => {
+        &[$(Signature {
+            content_type: ContentType::builtin($id),
+            offset: 0 $(+ $offset)?,
+            magic: Cow::Borrowed($magic),
+        },)*]
+    };
+}
+
+// More specific signatures come first, e.g. the brands of ISO media files.
+static SIGNATURES: &[Signature] = signatures! {
+    "com.adobe.pdf": b"%PDF-",
+    "public.png": b"\x89PNG\r\n\x1a\n",
+    "public.jpeg": b"\xff\xd8\xff",
+    "com.compuserve.gif": b"GIF87a",
+    "com.compuserve.gif": b"GIF89a",
+    "public.tiff": b"II*\0",
+    "public.tiff": b"MM\0*",
+    "org.webmproject.webp" @ 8: b"WEBP",
+    "public.heic" @ 4: b"ftypheic",
+    "public.heic" @ 4: b"ftypheix",
+    "public.heic" @ 4: b"ftypmif1",
+    "public.mpeg-4-audio" @ 4: b"ftypM4A ",
+    "com.apple.quicktime-movie" @ 4: b"ftypqt  ",
+    "public.mpeg-4" @ 4: b"ftyp",
+    "com.microsoft.waveform-audio" @ 8: b"WAVE",
+    "public.aiff-audio" @ 8: b"AIFF",
+    "org.xiph.flac": b"fLaC",
+    "public.mp3": b"ID3",
+    "org.openxmlformats.wordprocessingml.document": b"PK\x03\x04",
+    "org.openxmlformats.spreadsheetml.sheet": b"PK\x03\x04",
+    "org.openxmlformats.presentationml.presentation": b"PK\x03\x04",
+    "public.zip-archive": b"PK\x03\x04",
+    "org.gnu.gnu-zip-archive": b"\x1f\x8b",
+    "public.tar-archive" @ 257: b"ustar",
+};
+
+/// Detect the content type of `data` by its magic bytes, checking `custom`
+/// signatures before the built-in ones.
+pub fn detect_type(data: &[u8], custom: &[Signature]) -> Option<ContentType> {
+    custom
+        .iter()
+        .chain(SIGNATURES)
+        .find(|s| s.matches(data))
+        .map(|s| s.content_type.clone())
+}
+
+/// The result of inspecting the content of a picked file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sniffed {
+    /// The type detected by the magic bytes.
+    pub detected: Option<ContentType>,
+    /// Whether the content matches the requested types.
+    pub matches: bool,
+}
+
+/// Checks the picked files against the requested types.
+pub(crate) struct Sniffer {
+    policy: SniffPolicy,
+    requested: Vec<ContentType>,
+    // The requested extensions without a built-in type, without the dot.
+    unknown_extensions: Vec<String>,
+    signatures: Vec<Signature>,
+}
+
+impl Sniffer {
+    pub fn new(options: &PickOptions) -> Self {
+        let mut requested = options.content_types.clone();
+        let mut unknown_extensions = vec![];
+        for extension in &options.extensions {
+            match ContentType::from_extension(extension) {
+                Ok(content_type) => requested.push(content_type),
+                Err(_) => unknown_extensions
+                    .push(extension.strip_prefix('.').unwrap_or(extension).to_string()),
+            }
+        }
+        // Files reported without content can't be checked.
+        let policy = if options.streaming {
+            SniffPolicy::Off
+        } else {
+            options.sniff
+        };
+        Self {
+            policy,
+            requested,
+            unknown_extensions,
+            signatures: options.signatures.clone(),
+        }
+    }
+
+    fn signatures(&self) -> impl Iterator<Item = &Signature> {
+        self.signatures.iter().chain(SIGNATURES)
+    }
+
+    /// Content of a requested type without any signature, like plain text,
+    /// can't be judged, and so only a known signature of another type is a
+    /// mismatch. Extensions without a known type are compared with the file
+    /// name instead, and only match content without a known signature.
+    fn sniff(&self, name: &str, data: &[u8]) -> Sniffed {
+        let detected = self
+            .signatures()
+            .find(|s| s.matches(data))
+            .map(|s| s.content_type.clone());
+        let compatible = |t: &ContentType| {
+            self.requested
+                .iter()
+                .any(|r| t.conforms_to(r) || r.conforms_to(t))
+        };
+        let unknown_extension = Path::new(name)
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| {
+                self.unknown_extensions
+                    .iter()
+                    .any(|u| u.eq_ignore_ascii_case(e))
+            });
+        let matches = (self.requested.is_empty() && self.unknown_extensions.is_empty())
+            || self
+                .signatures()
+                .any(|s| s.matches(data) && compatible(&s.content_type))
+            || (detected.is_none()
+                && (unknown_extension
+                    || !self
+                        .requested
+                        .iter()
+                        .all(|r| self.signatures().any(|s| s.content_type.conforms_to(r)))));
+        Sniffed { detected, matches }
+    }
+
+    pub fn check(&self, file: PickedFile) -> Result<PickedFile, PickError> {
+        if self.policy == SniffPolicy::Off {
+            return Ok(file);
+        }
+        let sniffed = self.sniff(&file.metadata().name, &file);
+        if self.policy == SniffPolicy::Reject && !sniffed.matches {
+            return Err(PickError::TypeMismatch {
+                name: file.metadata().name.clone(),
+                detected: sniffed.detected,
+            });
+        }
+        let (mut metadata, handle) = file.into_parts();
+        metadata.sniffed = Some(sniffed);
+        Ok(PickedFile::new(metadata, handle))
+    }
+}
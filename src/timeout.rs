@@ -0,0 +1,127 @@
+use crate::PickError;
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Condvar, Mutex},
+    task::{Context, Poll, Waker},
+    thread,
+    time::{Duration, Instant},
+};
+
+#[derive(Default)]
+struct TimerState {
+    fired: bool,
+    cancelled: bool,
+    waker: Option<Waker>,
+}
+
+#[derive(Default)]
+struct Timer {
+    state: Mutex<TimerState>,
+    cancel: Condvar,
+}
+
+/// Completes at a deadline, without depending on the timer of any runtime.
+///
+/// A thread waits for the deadline once it is first polled, which is
+/// affordable as there's one per pick, and exits when the deadline drops.
+pub(crate) struct Deadline {
+    at: Instant,
+    timer: Option<Arc<Timer>>,
+}
+
+impl Deadline {
+    pub fn after(duration: Duration) -> Self {
+        Self {
+            at: Instant::now() + duration,
+            timer: None,
+        }
+    }
+
+    fn start(at: Instant, timer: Arc<Timer>) {
+        thread::spawn(move || {
+            let mut state = timer.state.lock().unwrap();
+            loop {
+                if state.cancelled {
+                    return;
+                }
+                let now = Instant::now();
+                if now >= at {
+                    break;
+                }
+                state = timer.cancel.wait_timeout(state, at - now).unwrap().0;
+            }
+            state.fired = true;
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        });
+    }
+}
+
+impl Drop for Deadline {
+    fn drop(&mut self) {
+        if let Some(timer) = &self.timer {
+            timer.state.lock().unwrap().cancelled = true;
+            timer.cancel.notify_one();
+        }
+    }
+}
+
+impl Future for Deadline {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if Instant::now() >= self.at {
+            return Poll::Ready(());
+        }
+        let at = self.at;
+        let timer = self.timer.get_or_insert_with(|| {
+            let timer = Arc::new(Timer::default());
+            Self::start(at, timer.clone());
+            timer
+        });
+        let mut state = timer.state.lock().unwrap();
+        if state.fired {
+            Poll::Ready(())
+        } else {
+            state.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+/// Fail `f` with [`PickError::TimedOut`] if it doesn't complete before the
+/// deadline, if any.
+pub(crate) struct Timeout<F> {
+    f: Pin<Box<F>>,
+    deadline: Option<Deadline>,
+}
+
+impl<F> Timeout<F> {
+    pub fn new(f: F, timeout: Option<Duration>) -> Self {
+        Self {
+            f: Box::pin(f),
+            deadline: timeout.map(Deadline::after),
+        }
+    }
+}
+
+impl<T, F: Future<Output = Result<T, PickError>>> Future for Timeout<F> {
+    type Output = Result<T, PickError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Poll::Ready(res) = self.f.as_mut().poll(cx) {
+            return Poll::Ready(res);
+        }
+        let elapsed = self
+            .deadline
+            .as_mut()
+            .is_some_and(|deadline| Pin::new(deadline).poll(cx).is_ready());
+        if elapsed {
+            Poll::Ready(Err(PickError::TimedOut))
+        } else {
+            Poll::Pending
+        }
+    }
+}
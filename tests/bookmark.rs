@@ -0,0 +1,105 @@
+mod common;
+
+use common::TempPath;
+use file_picker_ios::{
+    backend::{FakeBackend, FakeFile, FakeResponse},
+    open_bookmark_with, Bookmark, BookmarkStore, FilePicker, PickError, Presenter,
+};
+use std::{fs, io, sync::Arc};
+
+#[tokio::test]
+async fn pick_file_bookmark() {
+    let backend = FakeBackend::new();
+    backend.push_response(FakeResponse::picked([FakeFile::named("a.txt", "hello")]));
+    backend.push_response(FakeResponse::picked([FakeFile::named("a.txt", "hello")]));
+    let picker = FilePicker::new().backend(Arc::new(backend.clone()));
+
+    let file = picker.pick_file(&Presenter::default()).await.unwrap();
+    assert_eq!(file.metadata().bookmark, None);
+
+    let file = picker
+        .bookmarks(true)
+        .pick_file(&Presenter::default())
+        .await
+        .unwrap();
+    let bookmark = file.metadata().bookmark.clone().unwrap();
+    let reopened = open_bookmark_with(&backend, &bookmark).await.unwrap();
+    assert_eq!(&*reopened, b"hello");
+    assert_eq!(reopened.metadata().bookmark, Some(bookmark));
+}
+
+#[tokio::test]
+async fn open_bookmark_unknown() {
+    let backend = FakeBackend::new();
+    let res = open_bookmark_with(&backend, &Bookmark::from_bytes("gone")).await;
+    assert!(matches!(res, Err(PickError::BookmarkFailed(_))));
+}
+
+#[tokio::test]
+async fn bookmark_store_persisted() {
+    let path = TempPath::new("persisted.bookmarks");
+    let mut store = BookmarkStore::open(&path.0).unwrap();
+    assert!(store.is_empty());
+    store.insert("a", Bookmark::from_bytes("bookmark a"));
+    store.insert("b", Bookmark::from_bytes(vec![0, 1, 2]));
+    store.save().unwrap();
+
+    let mut store = BookmarkStore::open(&path.0).unwrap();
+    assert_eq!(
+        store.iter().collect::<Vec<_>>(),
+        [
+            ("a", &Bookmark::from_bytes("bookmark a")),
+            ("b", &Bookmark::from_bytes(vec![0, 1, 2])),
+        ]
+    );
+    assert_eq!(store.remove("a"), Some(Bookmark::from_bytes("bookmark a")));
+    store.save().unwrap();
+    assert_eq!(BookmarkStore::open(&path.0).unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn bookmark_store_version() {
+    let path = TempPath::new("version.bookmarks");
+    fs::write(&path.0, b"FPBK\x02\0\0\0\0\0\0\0").unwrap();
+    let res = BookmarkStore::open(&path.0);
+    assert!(matches!(
+        res,
+        Err(PickError::Io {
+            kind: io::ErrorKind::InvalidData,
+            ..
+        })
+    ));
+
+    fs::write(&path.0, b"FPBK\x01\0\0\0\x01\0\0\0").unwrap();
+    assert!(BookmarkStore::open(&path.0).is_err());
+}
+
+#[tokio::test]
+async fn bookmark_store_refreshes_stale() {
+    let path = TempPath::new("stale.bookmarks");
+    let backend = FakeBackend::new();
+    let stale = Bookmark::from_bytes("stale");
+    let refreshed = Bookmark::from_bytes("refreshed");
+    backend.add_stale_bookmark(
+        stale.clone(),
+        refreshed.clone(),
+        FakeFile::named("a.txt", "hello"),
+    );
+
+    let mut store = BookmarkStore::open(&path.0).unwrap();
+    store.insert("a", stale);
+    let file = store.reopen_with(&backend, "a").await.unwrap();
+    assert_eq!(&*file, b"hello");
+    assert_eq!(store.get("a"), Some(&refreshed));
+    let saved = BookmarkStore::open(&path.0).unwrap();
+    assert_eq!(saved.get("a"), Some(&refreshed));
+
+    let res = store.reopen_with(&backend, "b").await;
+    assert!(matches!(
+        res,
+        Err(PickError::Io {
+            kind: io::ErrorKind::NotFound,
+            ..
+        })
+    ));
+}
@@ -0,0 +1,31 @@
+//! Fixtures shared by the integration tests.
+
+use std::{fs, path::PathBuf};
+
+/// A path in the temporary directory, removed with whatever is there
+/// before and after the test.
+pub struct TempPath(pub PathBuf);
+
+impl TempPath {
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!(
+            "file-picker-ios-test-{}-{name}",
+            std::process::id()
+        ));
+        let temp = Self(path);
+        temp.remove();
+        temp
+    }
+
+    fn remove(&self) {
+        fs::remove_dir_all(&self.0)
+            .or_else(|_| fs::remove_file(&self.0))
+            .ok();
+    }
+}
+
+impl Drop for TempPath {
+    fn drop(&mut self) {
+        self.remove();
+    }
+}
@@ -0,0 +1,95 @@
+use file_picker_ios::{
+    backend::{FakeBackend, FakeResponse},
+    ContentType, FileMetadata, FilePicker, PickError, Presenter,
+};
+use std::sync::Arc;
+
+#[test]
+fn content_type_from_inputs() {
+    assert_eq!(ContentType::from_extension("PDF"), Ok(ContentType::PDF));
+    assert_eq!(ContentType::from_extension(".pdf"), Ok(ContentType::PDF));
+    assert_eq!(
+        ContentType::from_mime_type("text/plain; charset=utf-8"),
+        Ok(ContentType::PLAIN_TEXT)
+    );
+    assert_eq!(
+        ContentType::from_identifier("public.image"),
+        Ok(ContentType::IMAGE)
+    );
+    assert_eq!(
+        "image/png".parse::<ContentType>().unwrap().identifier(),
+        "public.png"
+    );
+    assert_eq!(
+        ".jpeg"
+            .parse::<ContentType>()
+            .unwrap()
+            .preferred_extension(),
+        Some("jpg")
+    );
+    assert_eq!(
+        ContentType::PDF.preferred_mime_type(),
+        Some("application/pdf")
+    );
+}
+
+#[test]
+fn content_type_invalid() {
+    assert_eq!(
+        ContentType::from_extension("nope"),
+        Err(PickError::UnsupportedType("nope".to_string()))
+    );
+    assert!(ContentType::from_mime_type("application/x-nope").is_err());
+    for identifier in [
+        "",
+        "public",
+        "public..image",
+        "com.example/doc",
+        "com.ex ample",
+    ] {
+        assert!(
+            ContentType::from_identifier(identifier).is_err(),
+            "{identifier}"
+        );
+    }
+    let custom = ContentType::from_identifier("com.example.notebook").unwrap();
+    assert!(custom.conforms_to(&custom));
+    assert!(!custom.conforms_to(&ContentType::DATA));
+}
+
+#[test]
+fn content_type_conformance() {
+    let png = ContentType::from_extension("png").unwrap();
+    assert!(png.conforms_to(&ContentType::IMAGE));
+    assert!(png.conforms_to(&ContentType::DATA));
+    assert!(png.conforms_to(&ContentType::ITEM));
+    assert!(!png.conforms_to(&ContentType::AUDIO));
+    assert!(!ContentType::IMAGE.conforms_to(&png));
+
+    let mp3 = ContentType::from_mime_type("audio/mpeg").unwrap();
+    assert!(mp3.conforms_to(&ContentType::AUDIO));
+    assert!(!mp3.conforms_to(&ContentType::MOVIE));
+
+    let metadata = FileMetadata::new("song.MP3");
+    assert!(metadata.conforms_to(&ContentType::AUDIO));
+    let mut metadata = FileMetadata::new("notes");
+    metadata.content_type = Some("public.utf8-plain-text".to_string());
+    assert!(metadata.conforms_to(&ContentType::TEXT));
+    assert!(!FileMetadata::new("unknown.xyz").conforms_to(&ContentType::DATA));
+}
+
+#[tokio::test]
+async fn pick_file_content_types() {
+    let backend = FakeBackend::new();
+    backend.push_response(FakeResponse::cancelled());
+    FilePicker::new()
+        .content_types([ContentType::IMAGE, ContentType::PDF])
+        .backend(Arc::new(backend.clone()))
+        .pick_file(&Presenter::default())
+        .await
+        .ok();
+    assert_eq!(
+        backend.requests()[0].content_types,
+        [ContentType::IMAGE, ContentType::PDF]
+    );
+}
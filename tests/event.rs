@@ -0,0 +1,71 @@
+use file_picker_ios::{
+    backend::{FakeBackend, FakeFile, FakeResponse},
+    FilePicker, PickError, PickEvent, Presenter,
+};
+use futures_util::{future::join, StreamExt, TryStreamExt};
+use std::{sync::Arc, time::Duration};
+
+#[tokio::test]
+async fn pick_files_events() {
+    let backend = FakeBackend::new();
+    backend.push_response(FakeResponse::picked([
+        FakeFile::named("a.txt", "first"),
+        FakeFile::named("b.txt", "second"),
+    ]));
+    let picker = FilePicker::new().backend(Arc::new(backend.clone()));
+    let (files, events) = picker.pick_files_with_events(&Presenter::default());
+    let (files, events) = join(files.try_collect::<Vec<_>>(), events.collect::<Vec<_>>()).await;
+    assert_eq!(files.unwrap().len(), 2);
+    assert!(backend.requests()[0].events);
+
+    let file = |index, name: &str, size| {
+        [
+            PickEvent::FileStarted {
+                index,
+                name: name.to_string(),
+            },
+            PickEvent::Progress {
+                index,
+                completed: size,
+                total: size,
+            },
+            PickEvent::FileFinished { index },
+        ]
+    };
+    let mut expected = vec![PickEvent::Presented, PickEvent::Dismissed];
+    expected.extend(file(0, "a.txt", 5));
+    expected.extend(file(1, "b.txt", 6));
+    expected.push(PickEvent::Completed);
+    assert_eq!(events, expected);
+}
+
+#[tokio::test]
+async fn pick_file_events_cancelled() {
+    let backend = FakeBackend::new();
+    backend.push_response(FakeResponse::cancelled().delayed(Duration::from_millis(10)));
+    let picker = FilePicker::new().backend(Arc::new(backend));
+    let (file, events) = picker.pick_file_with_events(&Presenter::default());
+    let (file, events) = join(file, events.collect::<Vec<_>>()).await;
+    assert_eq!(file.unwrap_err(), PickError::Cancelled);
+    assert_eq!(
+        events,
+        [
+            PickEvent::Presented,
+            PickEvent::Dismissed,
+            PickEvent::Completed
+        ]
+    );
+}
+
+#[tokio::test]
+async fn pick_file_events_not_presented() {
+    let backend = FakeBackend::new();
+    backend.push_response(FakeResponse::failed(
+        PickError::NoPresenter.try_into().unwrap(),
+    ));
+    let picker = FilePicker::new().backend(Arc::new(backend.clone()));
+    let (file, events) = picker.pick_file_with_events(&Presenter::default());
+    assert_eq!(file.await.unwrap_err(), PickError::NoPresenter);
+    assert_eq!(events.collect::<Vec<_>>().await, [PickEvent::Completed]);
+    assert!(backend.requests()[0].events);
+}
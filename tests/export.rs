@@ -0,0 +1,65 @@
+use file_picker_ios::{
+    backend::{FakeBackend, FakeResponse},
+    export_files_with, save_file_with, PickError, Presenter,
+};
+use std::fs;
+
+#[tokio::test]
+async fn save_file_staged() {
+    let backend = FakeBackend::new();
+    backend.push_response(FakeResponse::exported([
+        "file:///private/var/mobile/Documents/report.txt",
+    ]));
+    let url = save_file_with(&backend, &Presenter::default(), b"hello", "../report.txt")
+        .await
+        .unwrap();
+    assert_eq!(url, "file:///private/var/mobile/Documents/report.txt");
+
+    let exports = backend.exports();
+    assert_eq!(exports.len(), 1);
+    assert_eq!(exports[0].contents, [b"hello"]);
+    let staged = &exports[0].paths[0];
+    assert_eq!(staged.file_name().unwrap(), "report.txt");
+    assert!(!staged.exists());
+}
+
+#[tokio::test]
+async fn save_file_cancelled() {
+    let backend = FakeBackend::new();
+    backend.push_response(FakeResponse::cancelled());
+    let res = save_file_with(&backend, &Presenter::default(), b"hello", "").await;
+    assert_eq!(res.unwrap_err(), PickError::Cancelled);
+    let staged = &backend.exports()[0].paths[0];
+    assert_eq!(staged.file_name().unwrap(), "Untitled");
+    assert!(!staged.exists());
+}
+
+#[tokio::test]
+async fn save_file_without_url() {
+    let backend = FakeBackend::new();
+    backend.push_response(FakeResponse::exported(Vec::<String>::new()));
+    let res = save_file_with(&backend, &Presenter::default(), b"hello", "report.txt").await;
+    assert!(matches!(res, Err(PickError::Io { .. })));
+}
+
+#[tokio::test]
+async fn export_files_copied() {
+    let dir = std::env::temp_dir().join(format!("file-picker-ios-test-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let paths = vec![dir.join("a.txt"), dir.join("b.txt")];
+    for path in &paths {
+        fs::write(path, "content").unwrap();
+    }
+    let backend = FakeBackend::new();
+    backend.push_response(FakeResponse::exported([
+        "file:///Documents/a.txt",
+        "file:///Documents/b.txt",
+    ]));
+    let urls = export_files_with(&backend, &Presenter::default(), &paths)
+        .await
+        .unwrap();
+    assert_eq!(urls, ["file:///Documents/a.txt", "file:///Documents/b.txt"]);
+    assert_eq!(backend.exports()[0].paths, paths);
+    assert!(paths.iter().all(|p| p.exists()));
+    fs::remove_dir_all(dir).unwrap();
+}
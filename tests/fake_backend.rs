@@ -0,0 +1,300 @@
+use file_picker_ios::{
+    backend::{FakeBackend, FakeError, FakeFile, FakeResponse},
+    pick_file, pick_file_with, pick_files_with, FileMetadata, FilePicker, NativeError, PickError,
+    PickMode, PresentationStyle, Presenter,
+};
+use futures_util::StreamExt;
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+#[tokio::test]
+async fn pick_file_cancelled() {
+    let backend = FakeBackend::new();
+    backend.push_response(FakeResponse::cancelled());
+    let file = pick_file_with(&backend, &Presenter::default(), &["txt"]).await;
+    assert_eq!(file.unwrap_err(), PickError::Cancelled);
+    let requests = backend.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].extensions, ["txt"]);
+    assert!(!requests[0].allow_multiple);
+}
+
+#[tokio::test]
+async fn pick_file_picked() {
+    let backend = FakeBackend::new();
+    backend.push_response(FakeResponse::picked([FakeFile::contents("hello")]));
+    let file = pick_file_with(&backend, &Presenter::default(), &["txt"]).await;
+    assert_eq!(file.as_deref(), Ok(&b"hello"[..]));
+}
+
+#[tokio::test]
+async fn pick_file_read_failed() {
+    let backend = FakeBackend::new();
+    let error = PickError::ReadFailed(NativeError {
+        domain: "NSCocoaErrorDomain".to_string(),
+        code: 256,
+        description: "The file couldn't be opened.".to_string(),
+    });
+    backend.push_response(FakeResponse::picked([FakeFile::Failed(
+        error.clone().try_into().unwrap(),
+    )]));
+    let file = pick_file_with(&backend, &Presenter::default(), &["txt"]).await;
+    assert_eq!(file.unwrap_err(), error);
+}
+
+#[test]
+fn fake_error_rejects_rust_errors() {
+    assert_eq!(
+        FakeError::new(PickError::TimedOut),
+        Err(PickError::TimedOut)
+    );
+    assert_eq!(
+        FakeError::new(PickError::Cancelled).unwrap_err(),
+        PickError::Cancelled
+    );
+    let error = FakeError::new(PickError::AccessDenied).unwrap();
+    assert_eq!(error.error(), &PickError::AccessDenied);
+}
+
+#[tokio::test]
+async fn pick_file_delayed() {
+    let backend = FakeBackend::new();
+    backend.push_response(
+        FakeResponse::picked([FakeFile::contents("hello")]).delayed(Duration::from_millis(50)),
+    );
+    let file = pick_file_with(&backend, &Presenter::default(), &["txt"]).await;
+    assert_eq!(file.as_deref(), Ok(&b"hello"[..]));
+}
+
+#[tokio::test]
+async fn pick_file_unsupported() {
+    let file = pick_file(&Presenter::default(), &["txt"]).await;
+    assert_eq!(file.unwrap_err(), PickError::UnsupportedPlatform);
+}
+
+#[tokio::test]
+async fn pick_files_delayed() {
+    let backend = FakeBackend::new();
+    backend.push_response(
+        FakeResponse::picked([FakeFile::contents("hello")]).delayed(Duration::from_millis(50)),
+    );
+    let files = pick_files_with(&backend, &Presenter::default(), &["txt", "md"])
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].as_deref(), Ok(&b"hello"[..]));
+    assert!(backend.requests()[0].allow_multiple);
+}
+
+#[tokio::test]
+async fn pick_files_access_denied() {
+    let backend = FakeBackend::new();
+    backend.push_response(FakeResponse::picked([
+        FakeFile::Failed(PickError::AccessDenied.try_into().unwrap()),
+        FakeFile::contents("hello"),
+    ]));
+    let files = pick_files_with(&backend, &Presenter::default(), &[])
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(files.len(), 2);
+    assert_eq!(files[0].as_deref(), Err(&PickError::AccessDenied));
+    assert_eq!(files[1].as_deref(), Ok(&b"hello"[..]));
+}
+
+#[tokio::test]
+async fn pick_files_cancelled() {
+    let backend = FakeBackend::new();
+    let files = pick_files_with(&backend, &Presenter::default(), &[])
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].as_deref(), Err(&PickError::Cancelled));
+}
+
+#[tokio::test]
+async fn pick_files_unsupported_type() {
+    let backend = FakeBackend::new();
+    backend.push_response(FakeResponse::failed(
+        PickError::UnsupportedType("foo".to_string())
+            .try_into()
+            .unwrap(),
+    ));
+    let files = pick_files_with(&backend, &Presenter::default(), &["foo"])
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(files.len(), 1);
+    assert_eq!(
+        files[0].as_deref(),
+        Err(&PickError::UnsupportedType("foo".to_string()))
+    );
+}
+
+#[tokio::test]
+async fn pick_files_in_order() {
+    let backend = FakeBackend::new();
+    backend.push_response(
+        FakeResponse::picked((0..20u8).map(|i| FakeFile::contents([i])))
+            .delayed(Duration::from_millis(1)),
+    );
+    let mut files = pick_files_with(&backend, &Presenter::default(), &[]);
+    let mut contents = vec![];
+    while let Some(file) = files.next().await {
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        contents.push(file.unwrap()[0]);
+    }
+    assert_eq!(contents, (0..20).collect::<Vec<_>>());
+}
+
+#[tokio::test]
+async fn pick_file_metadata() {
+    let backend = FakeBackend::new();
+    let mut metadata = FileMetadata::new("report.pdf");
+    metadata.content_type = Some("com.adobe.pdf".to_string());
+    metadata.size = Some(3);
+    metadata.modified = Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+    metadata.provider = Some("iCloud Drive".to_string());
+    backend.push_response(FakeResponse::picked([FakeFile::Contents(
+        metadata.clone(),
+        b"pdf".to_vec(),
+    )]));
+    let file = pick_file_with(&backend, &Presenter::default(), &["pdf"])
+        .await
+        .unwrap();
+    assert!(file.metadata().request.is_some());
+    metadata.request = file.metadata().request;
+    assert_eq!(file.metadata(), &metadata);
+    assert_eq!(file.metadata().extension(), Some("pdf"));
+    assert_eq!(&*file, b"pdf");
+}
+
+#[tokio::test]
+async fn file_picker_options() {
+    let backend = FakeBackend::new();
+    backend.push_response(FakeResponse::picked(
+        (0..5u8).map(|i| FakeFile::contents([i])),
+    ));
+    let picker = FilePicker::new()
+        .extensions(["png", "jpg"])
+        .max_selection(3)
+        .show_extensions(false)
+        .mode(PickMode::Copy)
+        .presentation_style(PresentationStyle::FormSheet)
+        .concurrent_reads(4)
+        .backend(Arc::new(backend.clone()));
+    let files = picker
+        .pick_files(&Presenter::default())
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(files.len(), 3);
+    assert_eq!(backend.requests(), [picker.options().clone()]);
+    assert_eq!(backend.requests()[0].concurrent_reads, 4);
+}
+
+#[tokio::test]
+async fn pick_files_size_limits() {
+    let backend = Arc::new(FakeBackend::new());
+    backend.push_response(FakeResponse::picked([
+        FakeFile::named("a.txt", "1234"),
+        FakeFile::named("big.txt", "123456789"),
+        FakeFile::named("b.txt", "12345"),
+        FakeFile::named("c.txt", "12"),
+    ]));
+    let files = FilePicker::new()
+        .max_file_size(8)
+        .byte_budget(7)
+        .backend(backend)
+        .pick_files(&Presenter::default())
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(files.len(), 4);
+    assert_eq!(files[0].as_deref(), Ok(&b"1234"[..]));
+    assert_eq!(
+        files[1].as_ref().unwrap_err(),
+        &PickError::FileTooLarge {
+            name: "big.txt".to_string(),
+            size: 9,
+            limit: 8,
+        }
+    );
+    assert_eq!(
+        files[2].as_ref().unwrap_err(),
+        &PickError::BudgetExceeded {
+            name: "b.txt".to_string(),
+            size: 5,
+            remaining: 3,
+        }
+    );
+    assert_eq!(files[3].as_deref(), Ok(&b"12"[..]));
+}
+
+#[tokio::test]
+async fn drop_dismisses_picker() {
+    let backend = Arc::new(FakeBackend::new());
+    backend.push_response(FakeResponse::picked([
+        FakeFile::contents("a"),
+        FakeFile::contents("b"),
+    ]));
+    backend.push_response(
+        FakeResponse::picked([FakeFile::contents("c")]).delayed(Duration::from_secs(60)),
+    );
+    let picker = FilePicker::new().backend(backend.clone());
+
+    let mut files = picker.pick_files(&Presenter::default());
+    assert_eq!(files.next().await.unwrap().as_deref(), Ok(&b"a"[..]));
+    drop(files);
+    assert_eq!(backend.dismissals(), 1);
+
+    drop(picker.pick_file(&Presenter::default()));
+    assert_eq!(backend.dismissals(), 2);
+}
+
+#[tokio::test]
+async fn pick_timed_out() {
+    let backend = Arc::new(FakeBackend::new());
+    for _ in 0..2 {
+        backend.push_response(
+            FakeResponse::picked([FakeFile::contents("a")]).delayed(Duration::from_secs(60)),
+        );
+    }
+    let picker = FilePicker::new()
+        .timeout(Duration::from_millis(20))
+        .backend(backend.clone());
+
+    let file = picker.pick_file(&Presenter::default()).await;
+    assert_eq!(file.unwrap_err(), PickError::TimedOut);
+    assert_eq!(backend.dismissals(), 1);
+
+    let files = picker
+        .pick_files(&Presenter::default())
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].as_ref().unwrap_err(), &PickError::TimedOut);
+    assert_eq!(backend.dismissals(), 2);
+}
+
+#[test]
+fn pick_without_runtime() {
+    let backend = FakeBackend::new();
+    backend.push_response(
+        FakeResponse::picked([FakeFile::contents("hello")]).delayed(Duration::from_millis(20)),
+    );
+    backend.push_response(FakeResponse::picked([
+        FakeFile::contents("a"),
+        FakeFile::contents("b"),
+    ]));
+    let file =
+        futures_executor::block_on(pick_file_with(&backend, &Presenter::default(), &["txt"]));
+    assert_eq!(file.as_deref(), Ok(&b"hello"[..]));
+
+    let files = futures_executor::block_on(
+        pick_files_with(&backend, &Presenter::default(), &[]).collect::<Vec<_>>(),
+    );
+    let files = files
+        .into_iter()
+        .map(|file| file.unwrap().to_vec())
+        .collect::<Vec<_>>();
+    assert_eq!(files, [b"a", b"b"]);
+}
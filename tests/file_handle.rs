@@ -0,0 +1,67 @@
+use bytes::Bytes;
+use file_picker_ios::{
+    backend::{FakeBackend, FakeFile, FakeResponse, RawPickedFile},
+    pick_file_with, FileHandle, Presenter,
+};
+use stable_deref_trait::StableDeref;
+use std::ptr::{null, null_mut};
+
+fn assert_stable_deref<T: StableDeref>() {}
+
+#[tokio::test]
+async fn file_handle_slice() {
+    assert_stable_deref::<FileHandle>();
+
+    let backend = FakeBackend::new();
+    backend.push_response(FakeResponse::picked([FakeFile::contents("hello world")]));
+    let (_, handle) = pick_file_with(&backend, &Presenter::default(), &["txt"])
+        .await
+        .unwrap()
+        .into_parts();
+
+    let world = handle.slice(6..);
+    assert_eq!(&*world, b"world");
+    assert_eq!(world.as_ptr(), handle[6..].as_ptr());
+    assert_eq!(&*world.slice(1..=2), b"or");
+    assert!(handle.slice(..0).is_empty());
+
+    drop(handle);
+    let bytes = Bytes::from(world.clone());
+    assert_eq!(bytes, "world");
+    assert_eq!(bytes.as_ptr(), world.as_ptr());
+}
+
+#[test]
+#[should_panic]
+fn file_handle_slice_out_of_bounds() {
+    FileHandle::from(b"hello".to_vec()).slice(3..6);
+}
+
+#[test]
+#[should_panic]
+fn file_handle_slice_overflow() {
+    FileHandle::from(b"hello".to_vec()).slice(..=usize::MAX);
+}
+
+#[test]
+fn raw_file_out_of_range_dates() {
+    let raw = RawPickedFile {
+        data: null(),
+        len: 0,
+        owner: null_mut(),
+        release: None,
+        name: null(),
+        url: null(),
+        path: null(),
+        bookmark: null(),
+        bookmark_len: 0,
+        content_type: null(),
+        size: -1,
+        created: 1e20,
+        modified: -1e20,
+        provider: null(),
+    };
+    let file = unsafe { raw.to_file() };
+    assert_eq!(file.metadata().created, None);
+    assert_eq!(file.metadata().modified, None);
+}
@@ -0,0 +1,200 @@
+mod common;
+
+use common::TempPath;
+use file_picker_ios::{
+    backend::{FakeBackend, FakeFile, FakeResponse},
+    EntryKind, FolderPicker, PickError, Presenter, SymlinkPolicy,
+};
+use std::{fs, path::Path, sync::Arc};
+
+/// A temporary folder with a few files.
+fn temp_folder(name: &str) -> TempPath {
+    let dir = TempPath::new(name);
+    let path = &dir.0;
+    fs::create_dir_all(path.join("docs/drafts")).unwrap();
+    fs::create_dir_all(path.join(".git")).unwrap();
+    fs::write(path.join("readme.md"), "readme").unwrap();
+    fs::write(path.join("notes.txt"), "notes").unwrap();
+    fs::write(path.join("docs/guide.md"), "guide").unwrap();
+    fs::write(path.join("docs/drafts/idea.md"), "idea").unwrap();
+    fs::write(path.join(".git/HEAD"), "ref").unwrap();
+    dir
+}
+
+fn backend_picking(path: &Path) -> Arc<FakeBackend> {
+    let backend = FakeBackend::new();
+    backend.push_response(FakeResponse::picked([FakeFile::folder(path)]));
+    Arc::new(backend)
+}
+
+async fn relative_paths(picker: FolderPicker) -> Vec<String> {
+    let folder = picker.pick_folder(&Presenter::default()).await.unwrap();
+    folder
+        .entries()
+        .map(|e| e.unwrap().relative_path().to_string_lossy().into_owned())
+        .collect()
+}
+
+#[tokio::test]
+async fn pick_folder_entries() {
+    let dir = temp_folder("entries");
+    let backend = backend_picking(&dir.0);
+    let folder = FolderPicker::new()
+        .backend(backend.clone())
+        .pick_folder(&Presenter::default())
+        .await
+        .unwrap();
+    assert_eq!(folder.path(), dir.0);
+    assert!(backend.requests()[0].folder);
+
+    let entries = folder.entries().collect::<Result<Vec<_>, _>>().unwrap();
+    let paths = entries
+        .iter()
+        .map(|e| e.relative_path().to_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        paths,
+        [
+            ".git",
+            ".git/HEAD",
+            "docs",
+            "docs/drafts",
+            "docs/drafts/idea.md",
+            "docs/guide.md",
+            "notes.txt",
+            "readme.md",
+        ]
+    );
+    assert_eq!(entries[2].kind(), EntryKind::Dir);
+    assert_eq!(entries[2].size(), None);
+    assert_eq!(entries[6].kind(), EntryKind::File);
+    assert_eq!(entries[6].size(), Some(5));
+
+    let file = entries[5].read().await.unwrap();
+    assert_eq!(&*file, b"guide");
+    assert_eq!(file.metadata().name, "guide.md");
+}
+
+#[tokio::test]
+async fn pick_folder_filters() {
+    let dir = temp_folder("filters");
+    let picker = FolderPicker::new()
+        .backend(backend_picking(&dir.0))
+        .include("**/*.md")
+        .exclude("docs/drafts");
+    assert_eq!(relative_paths(picker).await, ["docs/guide.md", "readme.md"]);
+
+    let picker = FolderPicker::new()
+        .backend(backend_picking(&dir.0))
+        .include("*.md");
+    assert_eq!(relative_paths(picker).await, ["readme.md"]);
+}
+
+#[tokio::test]
+async fn pick_folder_max_depth() {
+    let dir = temp_folder("max-depth");
+    let picker = FolderPicker::new()
+        .backend(backend_picking(&dir.0))
+        .exclude(".*")
+        .max_depth(1);
+    assert_eq!(
+        relative_paths(picker).await,
+        ["docs", "notes.txt", "readme.md"]
+    );
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn pick_folder_symlinks() {
+    let dir = temp_folder("symlinks");
+    std::os::unix::fs::symlink(dir.0.join("docs"), dir.0.join("link")).unwrap();
+    let picker = |policy| {
+        FolderPicker::new()
+            .backend(backend_picking(&dir.0))
+            .include("link*")
+            .symlinks(policy)
+    };
+    assert!(relative_paths(picker(SymlinkPolicy::Skip)).await.is_empty());
+    assert_eq!(
+        relative_paths(picker(SymlinkPolicy::Include)).await,
+        ["link"]
+    );
+    assert_eq!(
+        relative_paths(picker(SymlinkPolicy::Follow).include("link/*")).await,
+        ["link", "link/drafts", "link/guide.md"]
+    );
+}
+
+#[tokio::test]
+async fn pick_folder_invalid_pattern() {
+    let backend = FakeBackend::new();
+    let res = FolderPicker::new()
+        .backend(Arc::new(backend.clone()))
+        .include("[")
+        .pick_folder(&Presenter::default())
+        .await;
+    assert_eq!(res.unwrap_err(), PickError::InvalidPattern("[".to_string()));
+    assert!(backend.requests().is_empty());
+}
+
+#[tokio::test]
+async fn pick_folder_cancelled() {
+    let backend = FakeBackend::new();
+    backend.push_response(FakeResponse::cancelled());
+    let res = FolderPicker::new()
+        .backend(Arc::new(backend))
+        .pick_folder(&Presenter::default())
+        .await;
+    assert_eq!(res.unwrap_err(), PickError::Cancelled);
+}
+
+#[tokio::test]
+async fn folder_entry_edits() {
+    let dir = temp_folder("edits");
+    let folder = FolderPicker::new()
+        .backend(backend_picking(&dir.0))
+        .pick_folder(&Presenter::default())
+        .await
+        .unwrap();
+    let entry = |path: &str| {
+        folder
+            .entries()
+            .map(Result::unwrap)
+            .find(|e| e.relative_path() == Path::new(path))
+            .unwrap()
+    };
+
+    let guide = entry("docs/guide.md");
+    let renamed = guide.rename("manual.md").await.unwrap();
+    assert_eq!(renamed.relative_path(), Path::new("docs/manual.md"));
+    assert!(!dir.0.join("docs/guide.md").exists());
+    assert_eq!(&*renamed.read().await.unwrap(), b"guide");
+    assert!(matches!(
+        renamed.rename("../manual.md").await,
+        Err(PickError::Io { .. })
+    ));
+
+    let created = renamed.create_sibling("faq.md", b"faq").await.unwrap();
+    assert_eq!(created.relative_path(), Path::new("docs/faq.md"));
+    assert_eq!(created.size(), Some(3));
+    assert_eq!(fs::read(dir.0.join("docs/faq.md")).unwrap(), b"faq");
+    assert!(matches!(
+        renamed.create_sibling("faq.md", b"again").await,
+        Err(PickError::WriteFailed(_))
+    ));
+    folder.create_file("todo.txt", b"todo").await.unwrap();
+    assert_eq!(fs::read(dir.0.join("todo.txt")).unwrap(), b"todo");
+    for name in ["docs/todo.txt", "../todo.txt", ""] {
+        assert!(matches!(
+            folder.create_file(name, b"todo").await,
+            Err(PickError::Io { .. })
+        ));
+    }
+
+    let metadata = created.replace(b"answers").await.unwrap();
+    assert_eq!(metadata.size, Some(7));
+    assert_eq!(fs::read(dir.0.join("docs/faq.md")).unwrap(), b"answers");
+
+    entry("docs").delete().await.unwrap();
+    assert!(!dir.0.join("docs").exists());
+}
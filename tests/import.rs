@@ -0,0 +1,144 @@
+mod common;
+
+use common::TempPath;
+use file_picker_ios::{
+    backend::{FakeBackend, FakeFile, FakeResponse},
+    FilePicker, ImportManager, PickError, PickMode, Presenter,
+};
+use futures_util::StreamExt;
+use std::{fs, io, sync::Arc};
+
+fn names(manager: &ImportManager) -> Vec<&str> {
+    manager
+        .items()
+        .iter()
+        .map(|item| item.name.as_str())
+        .collect()
+}
+
+#[test]
+fn import_collision_free() {
+    let dir = TempPath::new("import-names");
+    let mut manager = ImportManager::open(&dir.0).unwrap();
+    fs::write(dir.0.join("notes"), "local").unwrap();
+    for (name, contents) in [
+        ("report.pdf", "1"),
+        ("Report.pdf", "2"),
+        ("report (1).pdf", "3"),
+        ("notes", "4"),
+        ("../a/b", "5"),
+        ("", "6"),
+        (".profile", "7"),
+        (".profile", "8"),
+    ] {
+        manager
+            .import_contents(name, "", contents.as_bytes())
+            .unwrap();
+    }
+    assert_eq!(
+        names(&manager),
+        [
+            "report.pdf",
+            "Report (1).pdf",
+            "report (2).pdf",
+            "notes (1)",
+            ".._a_b",
+            "Untitled",
+            ".profile",
+            ".profile (1)",
+        ]
+    );
+    assert_eq!(fs::read(dir.0.join("report (2).pdf")).unwrap(), b"3");
+    assert_eq!(fs::read(dir.0.join("notes")).unwrap(), b"local");
+}
+
+#[test]
+fn import_normalizes_unicode() {
+    let dir = TempPath::new("import-unicode");
+    let mut manager = ImportManager::open(&dir.0).unwrap();
+    let item = manager
+        .import_contents("Cafe\u{301}.txt", "", b"nfd")
+        .unwrap();
+    assert_eq!(item.name, "Caf\u{e9}.txt");
+    assert_eq!(item.original_name, "Cafe\u{301}.txt");
+    let item = manager
+        .import_contents("Caf\u{e9}.txt", "", b"nfc")
+        .unwrap();
+    assert_eq!(item.name, "Caf\u{e9} (1).txt");
+}
+
+#[test]
+fn import_manifest_persisted() {
+    let dir = TempPath::new("import-manifest");
+    let mut manager = ImportManager::open(&dir.0).unwrap();
+    manager
+        .import_contents("a.txt", "file:///a.txt", b"a")
+        .unwrap();
+    manager.import_contents("b.txt", "", b"bb").unwrap();
+
+    let mut reopened = ImportManager::open(&dir.0).unwrap();
+    assert_eq!(reopened.items(), manager.items());
+    assert_eq!(reopened.items()[0].url, "file:///a.txt");
+    assert_eq!(reopened.items()[1].size, 2);
+
+    let removed = reopened.remove("a.txt").unwrap().unwrap();
+    assert!(!removed.path.exists());
+    assert_eq!(reopened.remove("a.txt").unwrap(), None);
+    assert_eq!(names(&ImportManager::open(&dir.0).unwrap()), ["b.txt"]);
+
+    let leftovers = fs::read_dir(&dir.0)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().into_string().unwrap())
+        .filter(|name| name.ends_with(".tmp"))
+        .count();
+    assert_eq!(leftovers, 0);
+}
+
+#[test]
+fn import_manifest_invalid_time() {
+    let dir = TempPath::new("import-manifest-time");
+    let mut manager = ImportManager::open(&dir.0).unwrap();
+    manager.import_contents("a.txt", "", b"a").unwrap();
+    let manifest = dir.0.join(".import-manifest");
+    let data = fs::read(&manifest).unwrap();
+    let (rest, _) = data.split_at(data.len() - 12);
+
+    let corrupt = |secs: u64, nanos: u32| {
+        let data = [rest, &secs.to_le_bytes(), &nanos.to_le_bytes()].concat();
+        fs::write(&manifest, data).unwrap();
+        ImportManager::open(&dir.0).unwrap_err()
+    };
+    for (secs, nanos) in [(0, u32::MAX), (u64::MAX, 999_999_999), (u64::MAX, 0)] {
+        assert!(matches!(
+            corrupt(secs, nanos),
+            PickError::Io {
+                kind: io::ErrorKind::InvalidData,
+                ..
+            }
+        ));
+    }
+}
+
+#[tokio::test]
+async fn import_files_picked() {
+    let dir = TempPath::new("import-picked");
+    let mut manager = ImportManager::open(&dir.0).unwrap();
+    let backend = FakeBackend::new();
+    backend.push_response(FakeResponse::picked([
+        FakeFile::named("a.txt", "hello"),
+        FakeFile::Failed(PickError::AccessDenied.try_into().unwrap()),
+        FakeFile::named("a.txt", "again"),
+    ]));
+    let items = FilePicker::new()
+        .backend(Arc::new(backend.clone()))
+        .import_files(&Presenter::default(), &mut manager)
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(backend.requests()[0].mode, PickMode::Copy);
+    assert_eq!(items.len(), 3);
+    assert_eq!(items[1], Err(PickError::AccessDenied));
+    let second = items[2].as_ref().unwrap();
+    assert_eq!(second.name, "a (1).txt");
+    assert_eq!(fs::read(&second.path).unwrap(), b"again");
+    assert_eq!(names(&manager), ["a.txt", "a (1).txt"]);
+}
@@ -0,0 +1,85 @@
+use file_picker_ios::{
+    backend::{FakeBackend, FakeFile, FakeResponse},
+    FilePicker, PickMode, Presenter, SniffPolicy,
+};
+use futures_util::{AsyncReadExt, TryStreamExt};
+use std::sync::Arc;
+
+#[tokio::test]
+async fn lazy_file_read() {
+    let backend = FakeBackend::new();
+    backend.push_response(FakeResponse::picked([FakeFile::named(
+        "notes.txt",
+        "hello world",
+    )]));
+    let picker = FilePicker::new().backend(Arc::new(backend.clone()));
+    let file = picker.pick_lazy_file(&Presenter::default()).await.unwrap();
+    assert!(backend.requests()[0].streaming);
+    assert_eq!(file.metadata().name, "notes.txt");
+    assert_eq!(file.metadata().size, Some(11));
+
+    assert_eq!(file.read_range(6, 5).await.unwrap(), b"world");
+    assert_eq!(&*file.read().await.unwrap(), b"hello world");
+    assert_eq!(file.stat().await.unwrap().size, Some(11));
+
+    let mut contents = String::new();
+    file.reader().read_to_string(&mut contents).await.unwrap();
+    assert_eq!(contents, "hello world");
+}
+
+#[tokio::test]
+async fn lazy_files_outlive_stream() {
+    let backend = FakeBackend::new();
+    backend.push_response(FakeResponse::picked([
+        FakeFile::named("a.txt", "first"),
+        FakeFile::named("b.txt", "second"),
+    ]));
+    let picker = FilePicker::new().backend(Arc::new(backend.clone()));
+    let picker = picker.multiple(true);
+    let mut files = picker.pick_lazy_files(&Presenter::default());
+    let first = files.try_next().await.unwrap().unwrap();
+    drop(files);
+    assert_eq!(backend.dismissals(), 1);
+
+    let clone = first.clone();
+    drop(first);
+    assert_eq!(&*clone.read().await.unwrap(), b"first");
+}
+
+#[tokio::test]
+async fn lazy_file_not_sniffed() {
+    let backend = FakeBackend::new();
+    backend.push_response(FakeResponse::picked([FakeFile::named(
+        "doc.pdf", "%PDF-1.7",
+    )]));
+    let picker = FilePicker::new().backend(Arc::new(backend));
+    let file = picker
+        .extensions(["png"])
+        .sniff(SniffPolicy::Reject)
+        .pick_lazy_file(&Presenter::default())
+        .await
+        .unwrap();
+    assert_eq!(file.metadata().sniffed, None);
+}
+
+#[tokio::test]
+async fn edit_file_in_place() {
+    let backend = FakeBackend::new();
+    backend.push_response(FakeResponse::picked([FakeFile::named(
+        "notes.txt",
+        "hello",
+    )]));
+    let picker = FilePicker::new().backend(Arc::new(backend.clone()));
+    let file = picker
+        .mode(PickMode::Copy)
+        .pick_file_for_editing(&Presenter::default())
+        .await
+        .unwrap();
+    assert_eq!(backend.requests()[0].mode, PickMode::Open);
+
+    let metadata = file.write_all(b"hello world").await.unwrap();
+    assert_eq!(metadata.size, Some(11));
+    assert_eq!(&*file.read().await.unwrap(), b"hello world");
+    file.replace(b"bye").await.unwrap();
+    assert_eq!(file.read_range(0, 16).await.unwrap(), b"bye");
+}
@@ -0,0 +1,39 @@
+use file_picker_ios::{
+    backend::{FakeBackend, FakeResponse},
+    pick_file_with, PickError, Presenter,
+};
+use raw_window_handle::{
+    AppKitWindowHandle, HandleError, HasWindowHandle, RawWindowHandle, UiKitWindowHandle,
+    WindowHandle,
+};
+use std::ptr::NonNull;
+
+struct Window(RawWindowHandle);
+
+impl HasWindowHandle for Window {
+    fn window_handle(&self) -> Result<WindowHandle<'_>, HandleError> {
+        Ok(unsafe { WindowHandle::borrow_raw(self.0) })
+    }
+}
+
+#[test]
+fn presenter_from_window_handle() {
+    let uikit = Window(UiKitWindowHandle::new(NonNull::dangling()).into());
+    assert!(Presenter::from_window_handle(&uikit).is_ok());
+
+    let appkit = Window(AppKitWindowHandle::new(NonNull::dangling()).into());
+    assert_eq!(
+        Presenter::from_window_handle(&appkit).unwrap_err(),
+        PickError::UnsupportedPlatform
+    );
+}
+
+#[tokio::test]
+async fn pick_file_no_presenter() {
+    let backend = FakeBackend::new();
+    backend.push_response(FakeResponse::failed(
+        PickError::NoPresenter.try_into().unwrap(),
+    ));
+    let file = pick_file_with(&backend, &Presenter::key_window(), &["txt"]).await;
+    assert_eq!(file.unwrap_err(), PickError::NoPresenter);
+}
@@ -0,0 +1,134 @@
+use file_picker_ios::{
+    backend::{FakeBackend, FakeFile, FakeResponse},
+    FilePicker, PickError, Presenter,
+};
+use futures_util::{AsyncReadExt, AsyncSeekExt, TryStreamExt};
+use std::{io::SeekFrom, sync::Arc};
+
+#[tokio::test]
+async fn reader_read_to_end() {
+    let backend = FakeBackend::new();
+    backend.push_response(FakeResponse::picked([FakeFile::named(
+        "data.bin",
+        "hello world",
+    )]));
+    let picker = FilePicker::new().backend(Arc::new(backend.clone()));
+    let mut reader = picker
+        .pick_file_reader(&Presenter::default())
+        .await
+        .unwrap()
+        .chunk_size(4);
+    assert!(backend.requests()[0].streaming);
+    assert_eq!(reader.metadata().name, "data.bin");
+    assert_eq!(reader.metadata().size, Some(11));
+
+    let mut buf = [0; 3];
+    reader.read_exact(&mut buf).await.unwrap();
+    assert_eq!(&buf, b"hel");
+    let mut rest = vec![];
+    reader.read_to_end(&mut rest).await.unwrap();
+    assert_eq!(rest, b"lo world");
+    assert_eq!(reader.position(), 11);
+}
+
+#[tokio::test]
+async fn reader_seek() {
+    let backend = FakeBackend::new();
+    backend.push_response(FakeResponse::picked([FakeFile::named(
+        "data.bin",
+        "hello world",
+    )]));
+    let picker = FilePicker::new().backend(Arc::new(backend));
+    let mut reader = picker
+        .pick_file_reader(&Presenter::default())
+        .await
+        .unwrap();
+    assert_eq!(reader.seek(SeekFrom::End(-5)).await.unwrap(), 6);
+    let mut rest = String::new();
+    reader.read_to_string(&mut rest).await.unwrap();
+    assert_eq!(rest, "world");
+
+    assert_eq!(reader.seek(SeekFrom::Current(-11)).await.unwrap(), 0);
+    let mut buf = [0; 5];
+    reader.read_exact(&mut buf).await.unwrap();
+    assert_eq!(&buf, b"hello");
+    assert!(reader.seek(SeekFrom::Current(-6)).await.is_err());
+}
+
+#[tokio::test]
+async fn reader_chunks() {
+    let backend = FakeBackend::new();
+    backend.push_response(FakeResponse::picked([FakeFile::named(
+        "data.bin",
+        "hello world",
+    )]));
+    let picker = FilePicker::new().backend(Arc::new(backend));
+    let reader = picker
+        .pick_file_reader(&Presenter::default())
+        .await
+        .unwrap()
+        .chunk_size(4);
+    assert_eq!(reader.read_range(6, 100).await.unwrap(), b"world");
+    assert_eq!(reader.read_range(20, 4).await.unwrap(), b"");
+
+    let chunks = reader.try_collect::<Vec<_>>().await.unwrap();
+    assert_eq!(chunks, [&b"hell"[..], b"o wo", b"rld"]);
+}
+
+#[tokio::test]
+async fn reader_chunks_end_after_error() {
+    let backend = FakeBackend::new();
+    backend.push_response(FakeResponse::picked([FakeFile::named(
+        "data.bin",
+        "hello world",
+    )]));
+    let picker = FilePicker::new().backend(Arc::new(backend.clone()));
+    let mut reader = picker
+        .pick_file_reader(&Presenter::default())
+        .await
+        .unwrap()
+        .chunk_size(4);
+    assert_eq!(reader.try_next().await.unwrap().unwrap(), b"hell");
+    backend.revoke(reader.path());
+    assert!(reader.try_next().await.is_err());
+    assert_eq!(reader.try_next().await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn reader_cancelled() {
+    let backend = FakeBackend::new();
+    let res = FilePicker::new()
+        .backend(Arc::new(backend))
+        .pick_file_reader(&Presenter::default())
+        .await;
+    assert_eq!(res.unwrap_err(), PickError::Cancelled);
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn reader_tokio_io() {
+    use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+    let backend = FakeBackend::new();
+    backend.push_response(FakeResponse::picked([FakeFile::named(
+        "data.bin",
+        "hello world",
+    )]));
+    let picker = FilePicker::new().backend(Arc::new(backend));
+    let mut reader = picker
+        .pick_file_reader(&Presenter::default())
+        .await
+        .unwrap()
+        .chunk_size(4);
+    assert_eq!(
+        AsyncSeekExt::seek(&mut reader, SeekFrom::Start(6))
+            .await
+            .unwrap(),
+        6
+    );
+    let mut rest = String::new();
+    AsyncReadExt::read_to_string(&mut reader, &mut rest)
+        .await
+        .unwrap();
+    assert_eq!(rest, "world");
+}
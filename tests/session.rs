@@ -0,0 +1,90 @@
+use file_picker_ios::{
+    backend::{FakeBackend, FakeFile, FakeResponse, PickerSession, SessionPolicy},
+    pick_file_with, PickError, Presenter,
+};
+use futures_util::future::join;
+use std::{sync::Arc, time::Duration};
+
+#[tokio::test]
+async fn session_queues_requests() {
+    let backend = FakeBackend::new();
+    for contents in ["first", "second"] {
+        backend.push_response(
+            FakeResponse::picked([FakeFile::contents(contents)]).delayed(Duration::from_millis(50)),
+        );
+    }
+    let session = PickerSession::new(Arc::new(backend.clone())).policy(SessionPolicy::Queue);
+    let first = pick_file_with(&session, &Presenter::default(), &[]);
+    let second = pick_file_with(&session, &Presenter::default(), &[]);
+    assert_eq!(backend.requests().len(), 1);
+    assert_eq!(session.queued().len(), 1);
+    let current = session.current().unwrap();
+
+    let (first, second) = join(first, second).await;
+    let (first, second) = (first.unwrap(), second.unwrap());
+    assert_eq!(&*first, b"first");
+    assert_eq!(&*second, b"second");
+    assert_eq!(first.metadata().request, Some(current));
+    assert!(second.metadata().request > Some(current));
+    assert_eq!(backend.requests().len(), 2);
+    assert_eq!(session.current(), None);
+}
+
+#[tokio::test]
+async fn session_rejects_busy() {
+    let backend = FakeBackend::new();
+    for contents in ["first", "second"] {
+        backend.push_response(
+            FakeResponse::picked([FakeFile::contents(contents)]).delayed(Duration::from_millis(50)),
+        );
+    }
+    let session = PickerSession::new(Arc::new(backend.clone())).policy(SessionPolicy::RejectBusy);
+    let first = pick_file_with(&session, &Presenter::default(), &[]);
+    let current = session.current().unwrap();
+    let second = pick_file_with(&session, &Presenter::default(), &[]).await;
+    assert_eq!(second.unwrap_err(), PickError::Busy { current });
+    assert_eq!(&*first.await.unwrap(), b"first");
+    assert_eq!(backend.requests().len(), 1);
+
+    // A fake backend reports it like the native side.
+    let busy = FakeBackend::new();
+    busy.push_response(FakeResponse::failed(
+        PickError::Busy { current }.try_into().unwrap(),
+    ));
+    let res = pick_file_with(&busy, &Presenter::default(), &[]).await;
+    assert_eq!(res.unwrap_err(), PickError::Busy { current });
+}
+
+#[tokio::test]
+async fn session_cancels_current() {
+    let backend = FakeBackend::new();
+    for contents in ["first", "second"] {
+        backend.push_response(
+            FakeResponse::picked([FakeFile::contents(contents)]).delayed(Duration::from_millis(50)),
+        );
+    }
+    let session =
+        PickerSession::new(Arc::new(backend.clone())).policy(SessionPolicy::CancelCurrent);
+    let first = pick_file_with(&session, &Presenter::default(), &[]);
+    let second = pick_file_with(&session, &Presenter::default(), &[]);
+    let (first, second) = join(first, second).await;
+    assert_eq!(first.unwrap_err(), PickError::Cancelled);
+    assert_eq!(&*second.unwrap(), b"second");
+    assert_eq!(backend.dismissals(), 1);
+}
+
+#[tokio::test]
+async fn session_drops_queued_request() {
+    let backend = FakeBackend::new();
+    for contents in ["first", "second"] {
+        backend.push_response(
+            FakeResponse::picked([FakeFile::contents(contents)]).delayed(Duration::from_millis(50)),
+        );
+    }
+    let session = PickerSession::new(Arc::new(backend.clone())).policy(SessionPolicy::Queue);
+    let first = pick_file_with(&session, &Presenter::default(), &[]);
+    drop(pick_file_with(&session, &Presenter::default(), &[]));
+    assert!(session.queued().is_empty());
+    assert_eq!(&*first.await.unwrap(), b"first");
+    assert_eq!(backend.requests().len(), 1);
+}
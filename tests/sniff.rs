@@ -0,0 +1,129 @@
+use file_picker_ios::{
+    backend::{FakeBackend, FakeFile, FakeResponse},
+    detect_type, ContentType, FilePicker, PickError, Presenter, Signature, SniffPolicy,
+};
+use futures_util::StreamExt;
+use std::sync::Arc;
+
+const PDF: &[u8] = b"%PDF-1.7\n";
+const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+
+#[test]
+fn detect_builtin_and_custom() {
+    assert_eq!(detect_type(PDF, &[]), Some(ContentType::PDF));
+    assert_eq!(
+        detect_type(b"\0\0\0\x18ftypheic", &[]).map(|t| t.to_string()),
+        Some("public.heic".to_string())
+    );
+    assert_eq!(detect_type(b"hello", &[]), None);
+
+    let sketch = ContentType::from_identifier("com.example.sketch").unwrap();
+    let signature = Signature::new(sketch.clone(), *b"SKCH").at(2);
+    assert_eq!(detect_type(b"\x01\x00SKCH", &[signature]), Some(sketch));
+}
+
+#[tokio::test]
+async fn sniff_flags_mismatch() {
+    let backend = Arc::new(FakeBackend::new());
+    backend.push_response(FakeResponse::picked([
+        FakeFile::named("a.pdf", PDF),
+        FakeFile::named("b.pdf", PNG),
+        FakeFile::named("c.txt", "plain text"),
+    ]));
+    let files = FilePicker::new()
+        .extensions(["pdf", "txt"])
+        .sniff(SniffPolicy::Flag)
+        .backend(backend)
+        .pick_files(&Presenter::default())
+        .collect::<Vec<_>>()
+        .await;
+    let sniffed = files
+        .into_iter()
+        .map(|file| file.unwrap().metadata().sniffed.clone().unwrap())
+        .map(|s| (s.detected.map(|t| t.to_string()), s.matches))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        sniffed,
+        [
+            (Some("com.adobe.pdf".to_string()), true),
+            (Some("public.png".to_string()), false),
+            (None, true),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn sniff_rejects_mismatch() {
+    let backend = Arc::new(FakeBackend::new());
+    backend.push_response(FakeResponse::picked([FakeFile::named("photo.png", PDF)]));
+    backend.push_response(FakeResponse::picked([FakeFile::named("photo.png", "text")]));
+    let picker = FilePicker::new()
+        .content_types([ContentType::IMAGE])
+        .sniff(SniffPolicy::Reject)
+        .backend(backend);
+    assert_eq!(
+        picker.pick_file(&Presenter::default()).await.unwrap_err(),
+        PickError::TypeMismatch {
+            name: "photo.png".to_string(),
+            detected: Some(ContentType::PDF),
+        }
+    );
+    // Images are recognizable, so unknown content doesn't match either.
+    assert_eq!(
+        picker.pick_file(&Presenter::default()).await.unwrap_err(),
+        PickError::TypeMismatch {
+            name: "photo.png".to_string(),
+            detected: None,
+        }
+    );
+}
+
+#[tokio::test]
+async fn sniff_custom_signature() {
+    let sketch = ContentType::from_identifier("com.example.sketch").unwrap();
+    let backend = Arc::new(FakeBackend::new());
+    backend.push_response(FakeResponse::picked([FakeFile::named("a.sketch", "SKCH1")]));
+    let file = FilePicker::new()
+        .content_types([sketch.clone()])
+        .sniff(SniffPolicy::Reject)
+        .signature(Signature::new(sketch.clone(), *b"SKCH"))
+        .backend(backend)
+        .pick_file(&Presenter::default())
+        .await
+        .unwrap();
+    let sniffed = file.metadata().sniffed.clone().unwrap();
+    assert_eq!(sniffed.detected, Some(sketch));
+    assert!(sniffed.matches);
+}
+
+#[tokio::test]
+async fn sniff_unknown_extension() {
+    let backend = Arc::new(FakeBackend::new());
+    backend.push_response(FakeResponse::picked([
+        FakeFile::named("a.xyz", "custom data"),
+        FakeFile::named("b.xyz", PDF),
+        FakeFile::named("c.bin", "custom data"),
+    ]));
+    let files = FilePicker::new()
+        .extensions(["xyz"])
+        .sniff(SniffPolicy::Reject)
+        .backend(backend)
+        .pick_files(&Presenter::default())
+        .collect::<Vec<_>>()
+        .await;
+    assert!(files[0].is_ok());
+    assert_eq!(
+        files[1].as_ref().unwrap_err(),
+        &PickError::TypeMismatch {
+            name: "b.xyz".to_string(),
+            detected: Some(ContentType::PDF),
+        }
+    );
+    assert_eq!(
+        files[2].as_ref().unwrap_err(),
+        &PickError::TypeMismatch {
+            name: "c.bin".to_string(),
+            detected: None,
+        }
+    );
+}